@@ -6,7 +6,6 @@ import {
   Transaction,
   TransactionInstruction,
   sendAndConfirmTransaction,
-  SYSVAR_RENT_PUBKEY,
 } from '@solana/web3.js';
 
 import {
@@ -14,6 +13,7 @@ import {
   getPayer,
   getProgramId,
   deriveUserDataAccountAddress,
+  deriveConfigAddress,
   InitializeInstruction,
 } from './utils';
 
@@ -66,8 +66,8 @@ async function main() {
       keys: [
         { pubkey: payer.publicKey, isSigner: true, isWritable: true },
         { pubkey: userDataAccount, isSigner: false, isWritable: true },
-        { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: deriveConfigAddress(programId)[0], isSigner: false, isWritable: false },
       ],
       programId,
       data: instructionData,
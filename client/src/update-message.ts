@@ -10,6 +10,7 @@ import {
   getPayer,
   getProgramId,
   deriveUserDataAccountAddress,
+  deriveConfigAddress,
   UpdateMessageInstruction,
   UserData,
 } from './utils';
@@ -83,6 +84,7 @@ async function main() {
       keys: [
         { pubkey: payer.publicKey, isSigner: true, isWritable: true },
         { pubkey: userDataAccount, isSigner: false, isWritable: true },
+        { pubkey: deriveConfigAddress(programId)[0], isSigner: false, isWritable: false },
       ],
       programId,
       data: instructionData,
@@ -9,6 +9,7 @@ import {
   getPayer,
   getProgramId,
   deriveUserDataAccountAddress,
+  deriveConfigAddress,
   fetchUserData,
   UpdateMessageInstruction,
 } from './utils';
@@ -58,6 +59,7 @@ async function main() {
       keys: [
         { pubkey: payer.publicKey, isSigner: true, isWritable: false },
         { pubkey: userDataAccount, isSigner: false, isWritable: true },
+        { pubkey: deriveConfigAddress(programId)[0], isSigner: false, isWritable: false },
       ],
       programId,
       data: instructionData,
@@ -57,12 +57,13 @@ export class UserData {
 
   // Calculate the size of the account data
   static getSize(name: string, message: string): number {
+    // 1 byte for the layout version
     // 1 byte for is_initialized
     // 32 bytes for owner
     // 4 bytes for name length + name bytes
     // 4 bytes for message length + message bytes
     // 8 bytes for update_count
-    return 1 + 32 + 4 + name.length + 4 + message.length + 8;
+    return 1 + 1 + 32 + 4 + name.length + 4 + message.length + 8;
   }
 
   // Convert boolean to u8 for serialization
@@ -86,8 +87,13 @@ export class UserData {
       // Manual deserialization
       let offset = 0;
 
-      // Read is_initialized (1 byte)
-      const is_initialized = data[offset] === 1;
+      // Read layout version (1 byte), the fields below are shared by all versions
+      const version = data[offset] + 1;
+      offset += 1;
+      console.log('version:', version);
+
+      // Read flags (1 byte), bit 0 is is_initialized
+      const is_initialized = (data[offset] & 1) === 1;
       offset += 1;
       console.log('is_initialized:', is_initialized);
 
@@ -255,6 +261,11 @@ export function deriveUserDataAccountAddress(
   );
 }
 
+// Function to derive the program config address, passed last to every mutating instruction
+export function deriveConfigAddress(programId: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([Buffer.from('config')], programId);
+}
+
 // Function to fetch account data
 export async function fetchUserData(
   connection: Connection,
@@ -0,0 +1,104 @@
+use std::convert::TryInto;
+
+/// Size of an ed25519 public key
+pub const PUBKEY_BYTES: usize = 32;
+
+/// Size of an ed25519 signature
+pub const SIGNATURE_BYTES: usize = 64;
+
+// Layout of the ed25519 program's instruction data: a signature count, a
+// padding byte, then one set of u16 offsets per signature
+const OFFSETS_START: usize = 2;
+const OFFSETS_SIZE: usize = 14;
+
+// Instruction index meaning "this instruction", so the key, signature and
+// message must all be inline in the ed25519 instruction's own data
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Build ed25519 program instruction data verifying one signature, with the
+/// public key, signature and message inline
+///
+/// This is the layout `verify_ed25519_instruction` accepts, and the one
+/// produced by the SDKs' `new_ed25519_instruction` helpers.
+pub fn ed25519_instruction_data(
+    pubkey: &[u8; PUBKEY_BYTES],
+    signature: &[u8; SIGNATURE_BYTES],
+    message: &[u8],
+) -> Vec<u8> {
+    let public_key_offset = OFFSETS_START + OFFSETS_SIZE;
+    let signature_offset = public_key_offset + PUBKEY_BYTES;
+    let message_data_offset = signature_offset + SIGNATURE_BYTES;
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for value in [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_data_offset as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    data
+}
+
+/// Check that ed25519 program instruction data verifies exactly `signature`
+/// by `pubkey` over `message`
+///
+/// The runtime has already checked the signature itself by the time the
+/// program can see the instruction; this only checks that it covers the
+/// expected key, signature and message. Only a single signature with inline
+/// data is accepted.
+///
+/// ```
+/// use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+/// use solana_clean_demo::attest::{ed25519_instruction_data, verify_ed25519_instruction};
+///
+/// let secret = SecretKey::from_bytes(&[1u8; 32]).unwrap();
+/// let keypair = Keypair { public: PublicKey::from(&secret), secret };
+/// let pubkey = keypair.public.to_bytes();
+/// let signature = keypair.sign(b"Hello Solana!").to_bytes();
+/// let data = ed25519_instruction_data(&pubkey, &signature, b"Hello Solana!");
+///
+/// assert!(verify_ed25519_instruction(&data, &pubkey, &signature, b"Hello Solana!"));
+/// assert!(!verify_ed25519_instruction(&data, &pubkey, &signature, b"Hello Solana?"));
+/// assert!(!verify_ed25519_instruction(&data, &[8u8; 32], &signature, b"Hello Solana!"));
+/// assert!(!verify_ed25519_instruction(&data[..20], &pubkey, &signature, b"Hello Solana!"));
+/// ```
+pub fn verify_ed25519_instruction(
+    data: &[u8],
+    pubkey: &[u8; PUBKEY_BYTES],
+    signature: &[u8; SIGNATURE_BYTES],
+    message: &[u8],
+) -> bool {
+    if data.first() != Some(&1) {
+        return false;
+    }
+
+    let offset = |index: usize| -> Option<usize> {
+        let start = OFFSETS_START + index * 2;
+        let bytes = data.get(start..start + 2)?;
+        Some(u16::from_le_bytes(bytes.try_into().ok()?) as usize)
+    };
+    let field = |offset: usize, len: usize| data.get(offset..offset.checked_add(len)?);
+
+    let check = || -> Option<bool> {
+        let inline = [1, 3, 6]
+            .iter()
+            .all(|index| offset(*index) == Some(CURRENT_INSTRUCTION as usize));
+        Some(
+            inline
+                && field(offset(2)?, PUBKEY_BYTES)? == pubkey
+                && field(offset(0)?, SIGNATURE_BYTES)? == signature
+                && offset(5)? == message.len()
+                && field(offset(4)?, message.len())? == message,
+        )
+    };
+    check().unwrap_or(false)
+}
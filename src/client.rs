@@ -0,0 +1,226 @@
+//! Off-chain helpers for consuming program activity
+
+use std::str::FromStr;
+
+use constant_time_eq::constant_time_eq;
+use solana_program::{pubkey::Pubkey, rent::Rent};
+use thiserror::Error;
+
+use crate::{
+    instruction::{unpack_name_and_message, AccountDemoInstruction, MAX_BATCH_SIZE},
+    state::UserData,
+};
+
+/// Fields reported by the `event:update` line logged on UpdateMessage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub owner: Pubkey,
+    pub update_count: u64,
+    pub message_len: usize,
+}
+
+/// Find the first `event:update` line in a transaction's logs and parse it
+///
+/// Lines may carry the runtime's `Program log: ` prefix or be passed bare.
+///
+/// ```
+/// use solana_clean_demo::client::parse_outcome;
+///
+/// let logs = vec![
+///     "Program log: Instruction: UpdateMessage { message: hi }".to_string(),
+///     "Program log: event:update owner=11111111111111111111111111111111 update_count=2 message_len=2".to_string(),
+/// ];
+/// let outcome = parse_outcome(&logs).unwrap();
+/// assert_eq!(outcome.update_count, 2);
+/// assert_eq!(outcome.message_len, 2);
+///
+/// assert!(parse_outcome(&["Program log: unrelated".to_string()]).is_none());
+/// ```
+pub fn parse_outcome(logs: &[String]) -> Option<Outcome> {
+    logs.iter().find_map(|line| parse_update_event(line))
+}
+
+fn parse_update_event(line: &str) -> Option<Outcome> {
+    let line = line.strip_prefix("Program log: ").unwrap_or(line);
+    let fields = line.strip_prefix("event:update ")?;
+
+    let mut owner = None;
+    let mut update_count = None;
+    let mut message_len = None;
+    for field in fields.split_whitespace() {
+        let (key, value) = field.split_once('=')?;
+        match key {
+            "owner" => owner = Some(Pubkey::from_str(value).ok()?),
+            "update_count" => update_count = Some(value.parse().ok()?),
+            "message_len" => message_len = Some(value.parse().ok()?),
+            _ => {}
+        }
+    }
+
+    Some(Outcome {
+        owner: owner?,
+        update_count: update_count?,
+        message_len: message_len?,
+    })
+}
+
+/// Compare GetSummary return data against an expected encoding in constant time
+///
+/// ```
+/// use borsh::BorshSerialize;
+/// use solana_clean_demo::{client::summary_matches, state::Summary};
+///
+/// let expected = Summary { update_count: 2, name_len: 8, message_len: 13 }.try_to_vec().unwrap();
+/// let returned = Summary { update_count: 2, name_len: 8, message_len: 13 }.try_to_vec().unwrap();
+/// let stale = Summary { update_count: 1, name_len: 8, message_len: 13 }.try_to_vec().unwrap();
+///
+/// assert!(summary_matches(&returned, &expected));
+/// assert!(!summary_matches(&stale, &expected));
+/// ```
+pub fn summary_matches(returned: &[u8], expected: &[u8]) -> bool {
+    constant_time_eq(returned, expected)
+}
+
+/// Rent-exempt minimum for each candidate account size, in order
+///
+/// Lets a UI show how the cost grows as the user types a longer message.
+///
+/// ```
+/// use solana_clean_demo::{client::rent_table, state::UserData};
+/// use solana_program::rent::Rent;
+///
+/// let sizes: Vec<usize> = (0..=UserData::MAX_MESSAGE_LENGTH)
+///     .step_by(32)
+///     .map(|len| UserData::get_size("John Doe", &"m".repeat(len)))
+///     .collect();
+/// let rent = Rent::default();
+/// let table = rent_table(&rent, &sizes);
+///
+/// assert_eq!(table.len(), sizes.len());
+/// assert_eq!(table[0], rent.minimum_balance(sizes[0]));
+/// // A larger account never costs less
+/// assert!(table.windows(2).all(|pair| pair[0] <= pair[1]));
+/// ```
+pub fn rent_table(rent: &Rent, sizes: &[usize]) -> Vec<u64> {
+    sizes.iter().map(|size| rent.minimum_balance(*size)).collect()
+}
+
+/// Input that the program would reject, caught before sending
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("Name longer than {} bytes", UserData::MAX_NAME_LENGTH)]
+    NameTooLong,
+
+    #[error("Message longer than {} bytes", UserData::MAX_MESSAGE_LENGTH)]
+    MessageTooLong,
+
+    #[error("Slot index not below {}", UserData::MAX_SLOTS)]
+    SlotOutOfRange,
+
+    #[error("Alias longer than {} bytes", UserData::MAX_ALIAS_LENGTH)]
+    AliasTooLong,
+
+    #[error("More than {} entries in batch", MAX_BATCH_SIZE)]
+    BatchTooLarge,
+
+    #[error("Label longer than {} bytes", UserData::MAX_LABEL_LENGTH)]
+    LabelTooLong,
+
+    #[error("Packed name and message are malformed")]
+    MalformedPacked,
+}
+
+impl AccountDemoInstruction {
+    /// Check an instruction's inputs against the limits the program enforces
+    ///
+    /// Only checks that need no account state are done here; for example the
+    /// total size of all slots still depends on what is already stored.
+    ///
+    /// ```
+    /// use solana_clean_demo::client::ValidationError;
+    /// use solana_clean_demo::instruction::AccountDemoInstruction;
+    /// use solana_clean_demo::state::UserData;
+    ///
+    /// let valid = AccountDemoInstruction::Initialize {
+    ///     name: "n".repeat(UserData::MAX_NAME_LENGTH),
+    ///     message: "Hello Solana!".to_string(),
+    /// };
+    /// assert_eq!(valid.validate(), Ok(()));
+    ///
+    /// let long_name = AccountDemoInstruction::Initialize {
+    ///     name: "n".repeat(UserData::MAX_NAME_LENGTH + 1),
+    ///     message: "Hello Solana!".to_string(),
+    /// };
+    /// assert_eq!(long_name.validate(), Err(ValidationError::NameTooLong));
+    ///
+    /// let long_message = AccountDemoInstruction::UpdateMessage {
+    ///     message: "m".repeat(UserData::MAX_MESSAGE_LENGTH + 1),
+    /// };
+    /// assert_eq!(long_message.validate(), Err(ValidationError::MessageTooLong));
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            AccountDemoInstruction::Initialize { name, message }
+            | AccountDemoInstruction::InitializeWithReserve { name, message, .. } => {
+                validate_entry(name, message)
+            }
+            AccountDemoInstruction::InitializeRaw { packed } => {
+                let (name, message) =
+                    unpack_name_and_message(packed).map_err(|_| ValidationError::MalformedPacked)?;
+                validate_entry(&name, &message)
+            }
+            AccountDemoInstruction::UpdateMessage { message }
+            | AccountDemoInstruction::UpdateMessageIfChanged { message }
+            | AccountDemoInstruction::SignedUpdateMessage { message, .. }
+            | AccountDemoInstruction::UpdateMessageCompact { message }
+            | AccountDemoInstruction::SwapMessage { new: message } => validate_message(message),
+            AccountDemoInstruction::SetMessageAt { index, message } => {
+                validate_slot(*index)?;
+                validate_message(message)
+            }
+            AccountDemoInstruction::GetMessageAt { index } => validate_slot(*index),
+            AccountDemoInstruction::SetAlias { alias } => {
+                if alias.len() > UserData::MAX_ALIAS_LENGTH {
+                    return Err(ValidationError::AliasTooLong);
+                }
+                Ok(())
+            }
+            AccountDemoInstruction::SetLabel { label } => {
+                if label.len() > UserData::MAX_LABEL_LENGTH {
+                    return Err(ValidationError::LabelTooLong);
+                }
+                Ok(())
+            }
+            AccountDemoInstruction::InitializeMany { entries } => {
+                if entries.len() > MAX_BATCH_SIZE {
+                    return Err(ValidationError::BatchTooLarge);
+                }
+                entries
+                    .iter()
+                    .try_for_each(|(name, message)| validate_entry(name, message))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn validate_entry(name: &str, message: &str) -> Result<(), ValidationError> {
+    if name.len() > UserData::MAX_NAME_LENGTH {
+        return Err(ValidationError::NameTooLong);
+    }
+    validate_message(message)
+}
+
+fn validate_message(message: &str) -> Result<(), ValidationError> {
+    if message.len() > UserData::MAX_MESSAGE_LENGTH {
+        return Err(ValidationError::MessageTooLong);
+    }
+    Ok(())
+}
+
+fn validate_slot(index: u8) -> Result<(), ValidationError> {
+    if index as usize >= UserData::MAX_SLOTS {
+        return Err(ValidationError::SlotOutOfRange);
+    }
+    Ok(())
+}
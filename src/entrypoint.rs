@@ -2,16 +2,34 @@ use solana_program::{
     account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
 };
 
-use crate::processor::Processor;
+use crate::{error::AccountDemoError, instruction::MAX_INSTRUCTION_DATA_LEN, processor::Processor};
 
 // Program entrypoint
 entrypoint!(process_instruction);
 
-// Process instruction entrypoint
+/// Process instruction entrypoint
+///
+/// Oversized instruction data is rejected before any logging or decoding.
+///
+/// ```
+/// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+/// use solana_clean_demo::instruction::MAX_INSTRUCTION_DATA_LEN;
+/// use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+///
+/// let oversized = vec![0u8; MAX_INSTRUCTION_DATA_LEN + 1];
+/// assert_eq!(
+///     process_instruction(&Pubkey::new_unique(), &[], &oversized).unwrap_err(),
+///     ProgramError::from(AccountDemoError::InvalidInstructionData)
+/// );
+/// ```
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
+    if instruction_data.len() > MAX_INSTRUCTION_DATA_LEN {
+        return Err(AccountDemoError::InvalidInstructionData.into());
+    }
+
     Processor::process(program_id, accounts, instruction_data)
 }
@@ -1,28 +1,159 @@
 use solana_program::program_error::ProgramError;
 use thiserror::Error;
 
+/// Custom program errors, surfaced to clients as `ProgramError::Custom(code)`
+///
+/// The codes are part of the client ABI. Never reorder or renumber the
+/// variants; add new ones at the end with the next free code.
+///
+/// ```
+/// use solana_clean_demo::error::AccountDemoError;
+/// use solana_program::program_error::ProgramError;
+///
+/// // The pinned client ABI. The match is exhaustive, so a new variant
+/// // doesn't compile until it is given its code here.
+/// let pinned = |error: &AccountDemoError| match error {
+///     AccountDemoError::NotInitialized => 0,
+///     AccountDemoError::AlreadyInitialized => 1,
+///     AccountDemoError::DataTypeMismatch => 2,
+///     AccountDemoError::NotOwner => 3,
+///     AccountDemoError::InvalidInstructionData => 4,
+///     AccountDemoError::NameTooLong => 5,
+///     AccountDemoError::MessageTooLong => 6,
+///     AccountDemoError::SlotOutOfRange => 7,
+///     AccountDemoError::MessagesTooLong => 8,
+///     AccountDemoError::AliasTooLong => 9,
+///     AccountDemoError::InvalidName => 10,
+///     AccountDemoError::TooFrequent => 11,
+///     AccountDemoError::TagMismatch => 12,
+///     AccountDemoError::ChecksumMismatch => 13,
+///     AccountDemoError::AlreadyRentExempt => 14,
+///     AccountDemoError::BatchTooLarge => 15,
+///     AccountDemoError::ProgramPaused => 16,
+///     AccountDemoError::AccountTooLarge => 17,
+///     AccountDemoError::NoChange => 18,
+///     AccountDemoError::InvalidAttestation => 19,
+///     AccountDemoError::WouldBreakRentExemption => 20,
+///     AccountDemoError::SerializationFailed => 21,
+///     AccountDemoError::LabelTooLong => 22,
+///     AccountDemoError::InvalidPda => 23,
+///     AccountDemoError::BlankMessage => 24,
+/// };
+///
+/// let all = [
+///     AccountDemoError::NotInitialized,
+///     AccountDemoError::AlreadyInitialized,
+///     AccountDemoError::DataTypeMismatch,
+///     AccountDemoError::NotOwner,
+///     AccountDemoError::InvalidInstructionData,
+///     AccountDemoError::NameTooLong,
+///     AccountDemoError::MessageTooLong,
+///     AccountDemoError::SlotOutOfRange,
+///     AccountDemoError::MessagesTooLong,
+///     AccountDemoError::AliasTooLong,
+///     AccountDemoError::InvalidName,
+///     AccountDemoError::TooFrequent,
+///     AccountDemoError::TagMismatch,
+///     AccountDemoError::ChecksumMismatch,
+///     AccountDemoError::AlreadyRentExempt,
+///     AccountDemoError::BatchTooLarge,
+///     AccountDemoError::ProgramPaused,
+///     AccountDemoError::AccountTooLarge,
+///     AccountDemoError::NoChange,
+///     AccountDemoError::InvalidAttestation,
+///     AccountDemoError::WouldBreakRentExemption,
+///     AccountDemoError::SerializationFailed,
+///     AccountDemoError::LabelTooLong,
+///     AccountDemoError::InvalidPda,
+///     AccountDemoError::BlankMessage,
+/// ];
+/// let mut codes = Vec::new();
+/// for error in all {
+///     let code = pinned(&error);
+///     assert_eq!(ProgramError::from(error), ProgramError::Custom(code), "code {}", code);
+///     codes.push(code);
+/// }
+///
+/// // Codes are unique and leave no gaps
+/// codes.sort_unstable();
+/// assert_eq!(codes, (0..codes.len() as u32).collect::<Vec<_>>());
+/// ```
 #[derive(Error, Debug)]
 pub enum AccountDemoError {
     #[error("Account not initialized")]
-    NotInitialized,
+    NotInitialized = 0,
     
     #[error("Account already initialized")]
-    AlreadyInitialized,
+    AlreadyInitialized = 1,
     
     #[error("Data type mismatch")]
-    DataTypeMismatch,
+    DataTypeMismatch = 2,
     
     #[error("Not the account owner")]
-    NotOwner,
+    NotOwner = 3,
     
     #[error("Invalid instruction data")]
-    InvalidInstructionData,
+    InvalidInstructionData = 4,
     
     #[error("Name is too long")]
-    NameTooLong,
+    NameTooLong = 5,
     
     #[error("Message is too long")]
-    MessageTooLong,
+    MessageTooLong = 6,
+    
+    #[error("Message slot index out of range")]
+    SlotOutOfRange = 7,
+    
+    #[error("Total length of slot messages is too long")]
+    MessagesTooLong = 8,
+    
+    #[error("Alias is too long")]
+    AliasTooLong = 9,
+    
+    #[error("Name contains control characters")]
+    InvalidName = 10,
+    
+    #[error("Updates are too frequent")]
+    TooFrequent = 11,
+    
+    #[error("Tag does not match the message")]
+    TagMismatch = 12,
+    
+    #[error("Account data checksum mismatch")]
+    ChecksumMismatch = 13,
+    
+    #[error("Account is already rent exempt")]
+    AlreadyRentExempt = 14,
+    
+    #[error("Too many entries in batch")]
+    BatchTooLarge = 15,
+    
+    #[error("Program is paused")]
+    ProgramPaused = 16,
+    
+    #[error("Account size exceeds the maximum")]
+    AccountTooLarge = 17,
+    
+    #[error("New message is the same as the current one")]
+    NoChange = 18,
+    
+    #[error("Missing or mismatched ed25519 attestation")]
+    InvalidAttestation = 19,
+    
+    #[error("Withdrawal would leave the account below rent exemption")]
+    WouldBreakRentExemption = 20,
+    
+    #[error("Failed to serialize account data")]
+    SerializationFailed = 21,
+    
+    #[error("Label is too long")]
+    LabelTooLong = 22,
+    
+    #[error("Account is not the canonical PDA")]
+    InvalidPda = 23,
+    
+    #[error("Message is empty or only whitespace")]
+    BlankMessage = 24,
 }
 
 impl From<AccountDemoError> for ProgramError {
@@ -1,27 +1,1826 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use std::convert::TryFrom;
+use std::io;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::pubkey::Pubkey;
 
+use crate::{error::AccountDemoError, pda, state::UserData};
+
+/// Maximum number of accounts created by a single InitializeMany
+pub const MAX_BATCH_SIZE: usize = 4;
+
+/// Largest instruction data the program will try to decode
+///
+/// Sized for the biggest valid instruction, a full InitializeMany batch
+/// with maximum-length names and messages.
+///
+/// ```
+/// use borsh::BorshSerialize;
+/// use solana_clean_demo::instruction::{AccountDemoInstruction, MAX_BATCH_SIZE, MAX_INSTRUCTION_DATA_LEN};
+/// use solana_clean_demo::state::UserData;
+///
+/// let entry = ("n".repeat(UserData::MAX_NAME_LENGTH), "m".repeat(UserData::MAX_MESSAGE_LENGTH));
+/// let largest = AccountDemoInstruction::InitializeMany { entries: vec![entry; MAX_BATCH_SIZE] };
+/// assert_eq!(largest.try_to_vec().unwrap().len(), MAX_INSTRUCTION_DATA_LEN);
+/// ```
+pub const MAX_INSTRUCTION_DATA_LEN: usize = 1   // variant tag
+    + 4                                          // entries length prefix
+    + MAX_BATCH_SIZE * (4 + UserData::MAX_NAME_LENGTH + 4 + UserData::MAX_MESSAGE_LENGTH);
+
+/// Number of accounts Initialize expects
+///
+/// The processor rejects an instruction passing fewer accounts than its
+/// `required_accounts` with `NotEnoughAccountKeys`, before the handler runs.
+///
+/// ```
+/// use borsh::BorshSerialize;
+/// use solana_clean_demo::entrypoint::process_instruction;
+/// use solana_clean_demo::instruction::{AccountDemoInstruction, INITIALIZE_ACCOUNTS, UPDATE_ACCOUNTS};
+/// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+///
+/// let initialize = AccountDemoInstruction::Initialize { name: "John Doe".to_string(), message: "Hi".to_string() };
+/// let update = AccountDemoInstruction::UpdateMessage { message: "Hi".to_string() };
+/// assert_eq!(initialize.required_accounts().len(), INITIALIZE_ACCOUNTS);
+/// assert_eq!(update.required_accounts().len(), UPDATE_ACCOUNTS);
+///
+/// let program_id = Pubkey::new_unique();
+/// let keys: Vec<_> = (0..INITIALIZE_ACCOUNTS).map(|_| Pubkey::new_unique()).collect();
+/// let mut lamports = vec![0u64; keys.len()];
+/// let mut data = vec![vec![]; keys.len()];
+/// let accounts: Vec<_> = keys
+///     .iter()
+///     .zip(lamports.iter_mut())
+///     .zip(data.iter_mut())
+///     .map(|((key, lamports), data)| AccountInfo::new(key, true, true, lamports, data, &program_id, false, 0))
+///     .collect();
+///
+/// for (instruction, count) in [(initialize, INITIALIZE_ACCOUNTS), (update, UPDATE_ACCOUNTS)] {
+///     let ix = instruction.try_to_vec().unwrap();
+///     let result = process_instruction(&program_id, &accounts[..count - 1], &ix);
+///     assert_eq!(result, Err(ProgramError::NotEnoughAccountKeys));
+/// }
+/// ```
+pub const INITIALIZE_ACCOUNTS: usize = 4;
+
+/// Number of accounts UpdateMessage expects, see `INITIALIZE_ACCOUNTS`
+pub const UPDATE_ACCOUNTS: usize = 3;
+
+/// One account an instruction expects, in the order it must be passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountSpec {
+    pub name: &'static str,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountSpec {
+    const fn new(name: &'static str, is_signer: bool, is_writable: bool) -> Self {
+        AccountSpec { name, is_signer, is_writable }
+    }
+}
+
+const OWNER: AccountSpec = AccountSpec::new("owner", true, true);
+const OWNER_READONLY: AccountSpec = AccountSpec::new("owner", true, false);
+const USER_DATA: AccountSpec = AccountSpec::new("user data", false, true);
+const USER_DATA_READONLY: AccountSpec = AccountSpec::new("user data", false, false);
+const SYSTEM_PROGRAM: AccountSpec = AccountSpec::new("system program", false, false);
+const CONFIG: AccountSpec = AccountSpec::new("config", false, false);
+const CONFIG_WRITABLE: AccountSpec = AccountSpec::new("config", false, true);
+const SOURCE: AccountSpec = AccountSpec::new("source", false, true);
+const SOURCE_READONLY: AccountSpec = AccountSpec::new("source", false, false);
+const DESTINATION: AccountSpec = AccountSpec::new("destination", false, true);
+const PAYER: AccountSpec = AccountSpec::new("payer", true, true);
+const ADMIN: AccountSpec = AccountSpec::new("admin", true, false);
+const UPGRADE_AUTHORITY: AccountSpec = AccountSpec::new("upgrade authority", true, true);
+const PROGRAM_DATA: AccountSpec = AccountSpec::new("program data", false, false);
+const KEY: AccountSpec = AccountSpec::new("key", false, false);
+const ACCOUNT: AccountSpec = AccountSpec::new("account", false, false);
+const OWNER_UNSIGNED: AccountSpec = AccountSpec::new("owner", false, false);
+const INSTRUCTIONS_SYSVAR: AccountSpec = AccountSpec::new("instructions sysvar", false, false);
+
+/// Instructions the program understands, Borsh-encoded with the variant
+/// tag first
+///
+/// Every instruction that writes state, see `is_mutating`, takes the
+/// program Config PDA as its last account, after the accounts listed for
+/// it below, so the pause can be checked. Clients written before the
+/// Config PDA existed must append it.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub enum AccountDemoInstruction {
     /// Initialize a new UserData account
     /// 
+    /// The account must be the signer's PDA, and the signer is stored as
+    /// its owner.
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, clock::Clock, program_error::ProgramError, program_stubs, pubkey::Pubkey, rent::Rent};
+    ///
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock { unix_timestamp: 1_700_000_000, ..Clock::default() } };
+    ///         0
+    ///     }
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let system_key = solana_program::system_program::id();
+    /// let initialize = AccountDemoInstruction::Initialize { name: "John Doe".to_string(), message: "Hi".to_string() };
+    /// let initialize = initialize.try_to_vec().unwrap();
+    ///
+    /// // Accounts that already exist skip creation, so no CPI is needed
+    /// let run = |signer_key: Pubkey, data_key: Pubkey| {
+    ///     let (mut signer_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 1_000_000, 0, 0);
+    ///     let (mut signer_data, mut data_data, mut system_data, mut config_data) = (vec![], vec![0u8; 128], vec![], vec![]);
+    ///     let accounts = [
+    ///         AccountInfo::new(&signer_key, true, true, &mut signer_lamports, &mut signer_data, &system_key, false, 0),
+    ///         AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///         AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///         AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    ///     ];
+    ///     process_instruction(&program_id, &accounts, &initialize)?;
+    ///     let user_data = UserData::load_initialized(&accounts[1].data.borrow())?;
+    ///     let canonical_bump = user_data.is_canonical().then(|| user_data.bump);
+    ///     Ok::<_, ProgramError>((user_data.get_owner(), canonical_bump, user_data.name_updated_at, user_data.message_updated_at))
+    /// };
+    ///
+    /// // The stored owner is always the signer, the bump is recorded and both fields are stamped
+    /// let signer = Pubkey::new_unique();
+    /// let (signer_pda, bump) = pda::find_user_data_address(&signer, &program_id);
+    /// assert_eq!(run(signer, signer_pda), Ok((signer, Some(bump), 1_700_000_000, 1_700_000_000)));
+    ///
+    /// // Someone else's PDA can't be taken over
+    /// let (other_pda, _) = pda::find_user_data_address(&Pubkey::new_unique(), &program_id);
+    /// assert_eq!(run(signer, other_pda), Err(ProgramError::InvalidArgument));
+    ///
+    /// // The signer can't double as its own data account
+    /// assert_eq!(run(signer, signer), Err(ProgramError::InvalidArgument));
+    /// ```
+    /// 
+    /// `create_account` fails on an address that already holds lamports, so
+    /// a PDA someone pre-funded is topped up, allocated and assigned instead:
+    /// 
+    /// ```
+    /// use std::{convert::TryInto, sync::{Arc, Mutex}};
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{
+    ///     account_info::AccountInfo, clock::Clock, entrypoint::{deserialize, ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    ///     instruction::Instruction, program_stubs, program_utils::limited_deserialize, pubkey::Pubkey, rent::Rent,
+    ///     system_instruction::SystemInstruction,
+    /// };
+    ///
+    /// // Serve the sysvars, and run the system program CPIs, recording each
+    /// struct Stubs(Arc<Mutex<Vec<SystemInstruction>>>);
+    /// impl program_stubs::SyscallStubs for Stubs {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock::default() };
+    ///         0
+    ///     }
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    ///     fn sol_invoke_signed(&self, instruction: &Instruction, account_infos: &[AccountInfo], _: &[&[&[u8]]]) -> ProgramResult {
+    ///         let account = |index: usize| {
+    ///             let key = &instruction.accounts[index].pubkey;
+    ///             account_infos.iter().find(|info| info.key == key).unwrap()
+    ///         };
+    ///         let system_instruction = limited_deserialize::<SystemInstruction>(&instruction.data, 64).unwrap();
+    ///         match &system_instruction {
+    ///             SystemInstruction::Transfer { lamports } => {
+    ///                 **account(0).lamports.borrow_mut() -= lamports;
+    ///                 **account(1).lamports.borrow_mut() += lamports;
+    ///             }
+    ///             SystemInstruction::Allocate { space } => account(0).realloc(*space as usize, true)?,
+    ///             SystemInstruction::Assign { owner } => account(0).assign(owner),
+    ///             other => panic!("unexpected CPI {:?}", other),
+    ///         }
+    ///         self.0.lock().unwrap().push(system_instruction);
+    ///         Ok(())
+    ///     }
+    /// }
+    /// let cpis = Arc::new(Mutex::new(Vec::new()));
+    /// program_stubs::set_syscall_stubs(Box::new(Stubs(cpis.clone())));
+    ///
+    /// // Lay the accounts out the way the runtime serializes them, so the
+    /// // account data can be allocated in place
+    /// fn serialize(accounts: &[(Pubkey, bool, bool, u64, Vec<u8>, Pubkey)]) -> Vec<u64> {
+    ///     let mut input = (accounts.len() as u64).to_le_bytes().to_vec();
+    ///     for (key, is_signer, is_writable, lamports, data, owner) in accounts {
+    ///         input.extend_from_slice(&[0xff, *is_signer as u8, *is_writable as u8, 0, 0, 0, 0, 0]);
+    ///         input.extend_from_slice(key.as_ref());
+    ///         input.extend_from_slice(owner.as_ref());
+    ///         input.extend_from_slice(&lamports.to_le_bytes());
+    ///         input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    ///         input.extend_from_slice(data);
+    ///         input.resize((input.len() + MAX_PERMITTED_DATA_INCREASE + 7) / 8 * 8, 0);
+    ///         input.extend_from_slice(&0u64.to_le_bytes()); // rent epoch
+    ///     }
+    ///     input.extend_from_slice(&[0u8; 8 + 32]); // no instruction data, program id
+    ///     input.chunks(8).map(|word| u64::from_ne_bytes(word.try_into().unwrap())).collect()
+    /// }
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let owner_key = Pubkey::new_unique();
+    /// let (data_key, _) = pda::find_user_data_address(&owner_key, &program_id);
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut input = serialize(&[
+    ///     (owner_key, true, true, 1_000_000_000, vec![], system_key),
+    ///     (data_key, false, true, 1000, vec![], system_key),
+    ///     (system_key, false, false, 0, vec![], Pubkey::default()),
+    ///     (config_key, false, false, 0, vec![], program_id),
+    /// ]);
+    /// let (_, accounts, _) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+    ///
+    /// let initialize = AccountDemoInstruction::Initialize { name: "John Doe".to_string(), message: "Hi".to_string() };
+    /// process_instruction(&program_id, &accounts, &initialize.try_to_vec().unwrap()).unwrap();
+    ///
+    /// let size = UserData::reserved_size("John Doe", "Hi", 0).unwrap();
+    /// let minimum = Rent::default().minimum_balance(size);
+    /// assert_eq!(
+    ///     *cpis.lock().unwrap(),
+    ///     [
+    ///         SystemInstruction::Transfer { lamports: minimum - 1000 },
+    ///         SystemInstruction::Allocate { space: size as u64 },
+    ///         SystemInstruction::Assign { owner: program_id },
+    ///     ]
+    /// );
+    /// assert_eq!(accounts[1].lamports(), minimum);
+    /// assert_eq!(*accounts[1].owner, program_id);
+    /// assert_eq!(UserData::load_initialized(&accounts[1].data.borrow()).unwrap().get_owner(), owner_key);
+    /// ```
+    /// 
     /// Accounts expected:
     /// 0. `[signer, writable]` The account owner
     /// 1. `[writable]` The UserData account to initialize
-    /// 2. `[]` The rent sysvar
-    /// 3. `[]` The system program
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
     Initialize { name: String, message: String },
     
     /// Update the message in a UserData account
     /// 
+    /// Updates must be at least `UserData::MIN_SLOT_GAP` slots apart.
+    /// 
+    /// ```
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, clock::Clock, program_error::ProgramError, program_stubs, pubkey::Pubkey};
+    ///
+    /// static SLOT: AtomicU64 = AtomicU64::new(100);
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock { slot: SLOT.load(Ordering::SeqCst), ..Clock::default() } };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    /// let mut data_data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut config_lamports) = (0, 0, 0);
+    /// let (mut owner_data, mut config_data) = (vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, false, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let update = |message: &str| {
+    ///     let ix = AccountDemoInstruction::UpdateMessage { message: message.to_string() }.try_to_vec().unwrap();
+    ///     process_instruction(&program_id, &accounts, &ix)
+    /// };
+    ///
+    /// update("First").unwrap();
+    ///
+    /// // A second update before the gap has passed is refused
+    /// SLOT.store(100 + UserData::MIN_SLOT_GAP - 1, Ordering::SeqCst);
+    /// assert_eq!(update("Again").unwrap_err(), ProgramError::from(AccountDemoError::TooFrequent));
+    ///
+    /// // Once it has, the update goes through
+    /// SLOT.store(100 + UserData::MIN_SLOT_GAP, Ordering::SeqCst);
+    /// update("Again").unwrap();
+    /// assert_eq!(UserData::load_initialized(&accounts[1].data.borrow()).unwrap().message, "Again");
+    /// ```
+    /// 
+    /// Accounts that can't hold the owner's UserData are rejected before
+    /// anything is read:
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let system_key = solana_program::system_program::id();
+    /// let update = AccountDemoInstruction::UpdateMessage { message: "Hi".to_string() }.try_to_vec().unwrap();
+    ///
+    /// // Update a valid UserData account of `owner_key` at `data_key`, owned by `data_owner`
+    /// let run = |owner_key: Pubkey, data_key: Pubkey, data_owner: Pubkey| {
+    ///     let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    ///     let mut data_data = vec![0u8; user_data.size_of_current()];
+    ///     user_data.save(&mut data_data).unwrap();
+    ///
+    ///     let (mut owner_lamports, mut data_lamports, mut config_lamports) = (0, 0, 0);
+    ///     let (mut owner_data, mut config_data) = (vec![], vec![]);
+    ///     let accounts = [
+    ///         AccountInfo::new(&owner_key, true, false, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///         AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &data_owner, false, 0),
+    ///         AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    ///     ];
+    ///     process_instruction(&program_id, &accounts, &update)
+    /// };
+    ///
+    /// // Data the program doesn't own could have been written by anyone
+    /// assert_eq!(
+    ///     run(Pubkey::new_unique(), Pubkey::new_unique(), system_key),
+    ///     Err(ProgramError::IncorrectProgramId)
+    /// );
+    ///
+    /// // Nor can the signer pass itself as the data account
+    /// let owner_key = Pubkey::new_unique();
+    /// assert_eq!(run(owner_key, owner_key, program_id), Err(ProgramError::InvalidArgument));
+    /// ```
+    /// 
     /// Accounts expected:
     /// 0. `[signer]` The account owner
     /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The program Config PDA
     UpdateMessage { message: String },
+    
+    /// Set the message stored in one of the UserData account's slots
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// // Leave room for the slots so the account needn't grow
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    /// user_data.messages = vec![];
+    /// let mut data = vec![0u8; user_data.size_of_current() + 64];
+    /// user_data.save(&mut data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 0, 0, 0);
+    /// let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let set_at = |index: u8, message: &str| {
+    ///     let ix = AccountDemoInstruction::SetMessageAt { index, message: message.to_string() };
+    ///     process_instruction(&program_id, &accounts, &ix.try_to_vec().unwrap())
+    /// };
+    /// set_at(0, "first").unwrap();
+    /// set_at(2, "third").unwrap();
+    ///
+    /// // Skipped slots are filled with empty messages
+    /// let stored = UserData::load_initialized(&accounts[1].data.borrow()).unwrap();
+    /// assert_eq!(stored.messages, ["first", "", "third"]);
+    ///
+    /// assert_eq!(
+    ///     set_at(UserData::MAX_SLOTS as u8, "too far"),
+    ///     Err(ProgramError::from(AccountDemoError::SlotOutOfRange))
+    /// );
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    SetMessageAt { index: u8, message: String },
+    
+    /// Log the message stored in one of the UserData account's slots
+    /// 
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError, instruction::AccountDemoInstruction, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, program_stubs, pubkey::Pubkey};
+    ///
+    /// struct CaptureLogs(Arc<Mutex<Vec<String>>>);
+    /// impl program_stubs::SyscallStubs for CaptureLogs {
+    ///     fn sol_log(&self, message: &str) {
+    ///         self.0.lock().unwrap().push(message.to_string());
+    ///     }
+    /// }
+    /// let logs = Arc::new(Mutex::new(Vec::new()));
+    /// program_stubs::set_syscall_stubs(Box::new(CaptureLogs(logs.clone())));
+    ///
+    /// let (program_id, owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    /// user_data.messages = vec!["first".to_string(), "second".to_string()];
+    /// let mut data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data).unwrap();
+    ///
+    /// let mut lamports = 0;
+    /// let account = AccountInfo::new(&data_key, false, false, &mut lamports, &mut data, &program_id, false, 0);
+    /// let get_at = |index: u8| {
+    ///     let ix = AccountDemoInstruction::GetMessageAt { index };
+    ///     process_instruction(&program_id, &[account.clone()], &ix.try_to_vec().unwrap())
+    /// };
+    /// get_at(1).unwrap();
+    /// // Slots that were never written read back as empty
+    /// get_at(3).unwrap();
+    ///
+    /// let logs = logs.lock().unwrap();
+    /// let logged = |entry: &str| logs.iter().any(|log| log == entry);
+    /// assert_eq!(logged("Message at slot 1: second"), cfg!(not(feature = "no-logs")));
+    /// assert_eq!(logged("Message at slot 3: "), cfg!(not(feature = "no-logs")));
+    /// drop(logs);
+    ///
+    /// assert_eq!(
+    ///     get_at(UserData::MAX_SLOTS as u8),
+    ///     Err(ProgramError::from(AccountDemoError::SlotOutOfRange))
+    /// );
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The UserData account to read
+    /// 1. `[]` Optional: the expected owner. When passed, the account must
+    ///    be that owner's canonical PDA
+    GetMessageAt { index: u8 },
+    
+    /// Set or clear (with an empty string) the display alias of a UserData account
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// // Leave room for the alias so the account needn't grow
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current() + UserData::MAX_ALIAS_LENGTH];
+    /// user_data.save(&mut data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 0, 0, 0);
+    /// let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let set_alias = |alias: &str| {
+    ///     let ix = AccountDemoInstruction::SetAlias { alias: alias.to_string() };
+    ///     process_instruction(&program_id, &accounts, &ix.try_to_vec().unwrap())?;
+    ///     Ok::<_, ProgramError>(UserData::load_initialized(&accounts[1].data.borrow())?.alias)
+    /// };
+    ///
+    /// assert_eq!(set_alias("johnny").unwrap(), "johnny");
+    /// assert_eq!(set_alias("").unwrap(), "");
+    /// assert_eq!(
+    ///     set_alias(&"a".repeat(UserData::MAX_ALIAS_LENGTH + 1)),
+    ///     Err(ProgramError::from(AccountDemoError::AliasTooLong))
+    /// );
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    SetAlias { alias: String },
+    
+    /// Close a UserData account, wiping its data and refunding its lamports
+    /// 
+    /// The account must have a verified bump, see CanonicalizePda. Later
+    /// instructions in the same transaction that read the closed account
+    /// fail with `NotInitialized`.
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let owner_key = Pubkey::new_unique();
+    /// let (data_key, bump) = pda::find_user_data_address(&owner_key, &program_id);
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data_data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut config_lamports) = (0, 1_000_000, 0);
+    /// let (mut owner_data, mut config_data) = (vec![], vec![]);
+    /// let owner = AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0);
+    /// let data = AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0);
+    /// let config = AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0);
+    ///
+    /// // Without a verified bump the account can't be closed yet
+    /// let close = AccountDemoInstruction::CloseAccount.try_to_vec().unwrap();
+    /// assert_eq!(
+    ///     process_instruction(&program_id, &[owner.clone(), data.clone(), config.clone()], &close).unwrap_err(),
+    ///     ProgramError::from(AccountDemoError::InvalidPda)
+    /// );
+    ///
+    /// user_data.set_canonical(bump);
+    /// user_data.save(&mut data.data.borrow_mut()).unwrap();
+    /// process_instruction(&program_id, &[owner.clone(), data.clone(), config.clone()], &close).unwrap();
+    /// assert_eq!(owner.lamports(), 1_000_000);
+    ///
+    /// let summary = AccountDemoInstruction::GetSummary.try_to_vec().unwrap();
+    /// assert_eq!(
+    ///     process_instruction(&program_id, &[data.clone()], &summary).unwrap_err(),
+    ///     ProgramError::from(AccountDemoError::NotInitialized)
+    /// );
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner, receives the lamports
+    /// 1. `[writable]` The UserData account to close
+    /// 2. `[]` The program Config PDA
+    CloseAccount,
+    
+    /// Append the source account's messages into the destination's slots,
+    /// then close the source and refund its lamports to the owner. The
+    /// source must have a verified bump, see CanonicalizePda
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let owner_key = Pubkey::new_unique();
+    /// let (source_key, bump) = pda::find_user_data_address(&owner_key, &program_id);
+    /// let destination_key = Pubkey::new_unique();
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut source = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    /// source.messages = vec!["a".to_string(), "b".to_string()];
+    /// source.set_canonical(bump);
+    /// let mut source_data = vec![0u8; source.size_of_current()];
+    /// source.save(&mut source_data).unwrap();
+    ///
+    /// // Leave the destination room for the merged slots so it needn't grow
+    /// let mut destination = UserData::new(owner_key, "John Doe".to_string(), "Hi".to_string()).unwrap();
+    /// destination.messages = vec!["x".to_string()];
+    /// let mut destination_data = vec![0u8; destination.size_of_current() + 64];
+    /// destination.save(&mut destination_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut source_lamports, mut destination_lamports, mut system_lamports, mut config_lamports) = (0, 5000, 0, 0, 0);
+    /// let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&source_key, false, true, &mut source_lamports, &mut source_data, &program_id, false, 0),
+    ///     AccountInfo::new(&destination_key, false, true, &mut destination_lamports, &mut destination_data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let merge = AccountDemoInstruction::MergeInto.try_to_vec().unwrap();
+    /// process_instruction(&program_id, &accounts, &merge).unwrap();
+    ///
+    /// // The source's message and slots follow the destination's own slots
+    /// let merged = UserData::load_initialized(&accounts[2].data.borrow()).unwrap();
+    /// assert_eq!(merged.messages, ["x", "Hello", "a", "b"]);
+    ///
+    /// // The source is wiped and its lamports went to the owner
+    /// assert!(accounts[1].data.borrow().iter().all(|byte| *byte == 0));
+    /// assert_eq!(accounts[1].lamports(), 0);
+    /// assert_eq!(accounts[0].lamports(), 5000);
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The owner of both accounts
+    /// 1. `[writable]` The source UserData account, closed afterwards
+    /// 2. `[writable]` The destination UserData account
+    /// 3. `[]` The system program
+    /// 4. `[]` The program Config PDA
+    MergeInto,
+    
+    /// Return the account's scalar fields as a Borsh-encoded `Summary`
+    /// via return data
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let owner_key = Pubkey::new_unique();
+    /// let (pda_key, _) = pda::find_user_data_address(&owner_key, &program_id);
+    /// let spoofed_key = Pubkey::new_unique();
+    /// let summary = AccountDemoInstruction::GetSummary.try_to_vec().unwrap();
+    ///
+    /// let read = |data_key: &Pubkey, check_owner: bool| {
+    ///     let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    ///     let mut data = vec![0u8; user_data.size_of_current()];
+    ///     user_data.save(&mut data).unwrap();
+    ///     let (mut data_lamports, mut owner_lamports, mut owner_data) = (1_000_000, 0, vec![]);
+    ///     let mut accounts = vec![AccountInfo::new(data_key, false, false, &mut data_lamports, &mut data, &program_id, false, 0)];
+    ///     if check_owner {
+    ///         accounts.push(AccountInfo::new(&owner_key, false, false, &mut owner_lamports, &mut owner_data, &owner_key, false, 0));
+    ///     }
+    ///     process_instruction(&program_id, &accounts, &summary)
+    /// };
+    ///
+    /// // Without the owner any program-owned account holding UserData is read
+    /// assert!(read(&spoofed_key, false).is_ok());
+    ///
+    /// // With it, only the owner's PDA is
+    /// assert!(read(&pda_key, true).is_ok());
+    /// assert_eq!(read(&spoofed_key, true).unwrap_err(), ProgramError::InvalidArgument);
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The UserData account to read
+    /// 1. `[]` Optional: the expected owner. When passed, the account must
+    ///    be that owner's canonical PDA
+    GetSummary,
+    
+    /// Verify a keyed tag over the account's message in constant time
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The UserData account to check
+    /// 1. `[]` Optional: the expected owner. When passed, the account must
+    ///    be that owner's canonical PDA
+    VerifyTag { expected: Vec<u8> },
+    
+    /// Replace the message and return the previous one via return data
+    /// 
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, clock::Clock, program_stubs, pubkey::Pubkey};
+    ///
+    /// // Keeps whatever the program returns, and serves a default Clock
+    /// struct Stubs(Arc<Mutex<Vec<u8>>>);
+    /// impl program_stubs::SyscallStubs for Stubs {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock::default() };
+    ///         0
+    ///     }
+    ///     fn sol_set_return_data(&self, data: &[u8]) {
+    ///         *self.0.lock().unwrap() = data.to_vec();
+    ///     }
+    /// }
+    /// let return_data = Arc::new(Mutex::new(Vec::new()));
+    /// program_stubs::set_syscall_stubs(Box::new(Stubs(return_data.clone())));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    /// let mut data_data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 0, 0, 0);
+    /// let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let swap = AccountDemoInstruction::SwapMessage { new: "World".to_string() }.try_to_vec().unwrap();
+    /// process_instruction(&program_id, &accounts, &swap).unwrap();
+    ///
+    /// // The old message comes back and the new one is stored
+    /// assert_eq!(*return_data.lock().unwrap(), b"Hello");
+    /// assert_eq!(UserData::load_initialized(&accounts[1].data.borrow()).unwrap().message, "World");
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    SwapMessage { new: String },
+    
+    /// Transfer exactly enough lamports to make a UserData account rent exempt
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError, instruction::AccountDemoInstruction, pda};
+    /// use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction, program_error::ProgramError};
+    /// use solana_program::{program_stubs, program_utils::limited_deserialize, pubkey::Pubkey, rent::Rent, system_instruction::SystemInstruction};
+    ///
+    /// // Serves a default Rent and carries out system transfers
+    /// struct Stubs;
+    /// impl program_stubs::SyscallStubs for Stubs {
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    ///     fn sol_invoke_signed(&self, instruction: &Instruction, account_infos: &[AccountInfo], _: &[&[&[u8]]]) -> ProgramResult {
+    ///         let lamports = match limited_deserialize(&instruction.data, 64) {
+    ///             Ok(SystemInstruction::Transfer { lamports }) => lamports,
+    ///             _ => panic!("unexpected CPI"),
+    ///         };
+    ///         let account = |index: usize| account_infos.iter().find(|info| *info.key == instruction.accounts[index].pubkey).unwrap();
+    ///         **account(0).lamports.borrow_mut() -= lamports;
+    ///         **account(1).lamports.borrow_mut() += lamports;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Stubs));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (payer_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    /// let minimum = Rent::default().minimum_balance(128);
+    ///
+    /// let (mut payer_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (minimum, 1000, 0, 0);
+    /// let (mut payer_data, mut data_data, mut system_data, mut config_data) = (vec![], vec![0u8; 128], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&payer_key, true, true, &mut payer_lamports, &mut payer_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let top_up = AccountDemoInstruction::TopUpRent.try_to_vec().unwrap();
+    ///
+    /// // Only the shortfall is transferred
+    /// process_instruction(&program_id, &accounts, &top_up).unwrap();
+    /// assert_eq!(accounts[1].lamports(), minimum);
+    /// assert_eq!(accounts[0].lamports(), 1000);
+    ///
+    /// // After that there is nothing left to pay
+    /// assert_eq!(
+    ///     process_instruction(&program_id, &accounts, &top_up).unwrap_err(),
+    ///     ProgramError::from(AccountDemoError::AlreadyRentExempt)
+    /// );
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The payer
+    /// 1. `[writable]` The UserData account to top up
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    TopUpRent,
+    
+    /// Initialize several UserData accounts, one per `(name, message)` entry.
+    /// More than `MAX_BATCH_SIZE` entries fail with `BatchTooLarge`
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::instruction::{AccountDemoInstruction, MAX_BATCH_SIZE};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let system_key = solana_program::system_program::id();
+    /// let (mut lamports, mut data) = (0, vec![]);
+    /// let system = AccountInfo::new(&system_key, false, false, &mut lamports, &mut data, &system_key, true, 0);
+    ///
+    /// // Reported as too large, not as short of accounts
+    /// let entries = vec![("a".to_string(), "b".to_string()); MAX_BATCH_SIZE + 1];
+    /// let initialize_many = AccountDemoInstruction::InitializeMany { entries }.try_to_vec().unwrap();
+    /// assert_eq!(
+    ///     process_instruction(&program_id, &[system], &initialize_many),
+    ///     Err(ProgramError::from(AccountDemoError::BatchTooLarge))
+    /// );
+    /// ```
+    /// 
+    /// Every entry of a batch is initialized by the one instruction:
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, clock::Clock, program_stubs, pubkey::Pubkey, rent::Rent};
+    ///
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock::default() };
+    ///         0
+    ///     }
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let system_key = solana_program::system_program::id();
+    /// let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    /// let pdas: Vec<Pubkey> = owners.iter().map(|owner| pda::find_user_data_address(owner, &program_id).0).collect();
+    ///
+    /// // The PDAs already exist, so no CPI is needed
+    /// let (mut owner_lamports, mut data_lamports) = ([0; 3], [1_000_000; 3]);
+    /// let (mut owner_data, mut data_data) = (vec![vec![]; 3], vec![vec![0u8; 128]; 3]);
+    /// let (mut system_lamports, mut config_lamports, mut system_data, mut config_data) = (0, 0, vec![], vec![]);
+    /// let mut accounts = vec![
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    /// ];
+    /// let owner_accounts = owners.iter().zip(owner_lamports.iter_mut()).zip(owner_data.iter_mut());
+    /// let data_accounts = pdas.iter().zip(data_lamports.iter_mut()).zip(data_data.iter_mut());
+    /// for (((owner_key, owner_lamports), owner_data), ((data_key, data_lamports), data_data)) in owner_accounts.zip(data_accounts) {
+    ///     accounts.push(AccountInfo::new(owner_key, true, true, owner_lamports, owner_data, &system_key, false, 0));
+    ///     accounts.push(AccountInfo::new(data_key, false, true, data_lamports, data_data, &program_id, false, 0));
+    /// }
+    /// accounts.push(AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0));
+    ///
+    /// let entries = (0..3).map(|i| (format!("User {}", i), format!("Hello {}", i))).collect();
+    /// let initialize_many = AccountDemoInstruction::InitializeMany { entries }.try_to_vec().unwrap();
+    /// process_instruction(&program_id, &accounts, &initialize_many).unwrap();
+    ///
+    /// for (i, owner_key) in owners.iter().enumerate() {
+    ///     let user_data = UserData::load_initialized(&accounts[2 + 2 * i].data.borrow()).unwrap();
+    ///     assert_eq!(user_data.get_owner(), *owner_key);
+    ///     assert_eq!(user_data.name, format!("User {}", i));
+    ///     assert_eq!(user_data.message, format!("Hello {}", i));
+    /// }
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The system program
+    /// 
+    /// Then, for each entry in order:
+    /// 0. `[signer, writable]` The account owner
+    /// 1. `[writable]` The UserData account to initialize
+    /// 
+    /// Followed by:
+    /// 0. `[]` The program Config PDA
+    InitializeMany { entries: Vec<(String, String)> },
+    
+    /// Pause or resume every mutating instruction
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The admin stored in the Config PDA by InitConfig
+    /// 1. `[writable]` The program Config PDA
+    SetPaused { paused: bool },
+    
+    /// Return whether a key owns a UserData account via return data,
+    /// as a single byte: 1 for the owner, 0 otherwise
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The UserData account to check
+    /// 1. `[]` The key to check, need not sign
+    CheckOwner,
+    
+    /// Set the owner-controlled flags selected by `mask` to the matching
+    /// bits of `value` in one step
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The account owner
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The program Config PDA
+    SetFlags { mask: u8, value: u8 },
+    
+    /// Initialize a new UserData account with `reserve_bytes` of unused
+    /// space, so later growth needs no realloc
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner
+    /// 1. `[writable]` The UserData account to initialize
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    InitializeWithReserve { name: String, message: String, reserve_bytes: u32 },
+    
+    /// Log the account's raw data as hex, for attaching to bug reports.
+    /// Only handled with the `debug-tools` feature; otherwise the tag is
+    /// rejected as unknown. The variant itself always exists so later tags
+    /// don't shift with the feature
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The account to dump
+    DumpRaw,
+    
+    /// Like UpdateMessage, but fail with `NoChange` when the new message
+    /// equals the current one
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data_data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut config_lamports) = (0, 0, 0);
+    /// let (mut owner_data, mut config_data) = (vec![], vec![]);
+    /// let owner = AccountInfo::new(&owner_key, true, false, &mut owner_lamports, &mut owner_data, &system_key, false, 0);
+    /// let data = AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0);
+    /// let config = AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0);
+    /// let accounts = [owner, data, config];
+    ///
+    /// let update = |message: &str| {
+    ///     let ix = AccountDemoInstruction::UpdateMessageIfChanged { message: message.to_string() };
+    ///     process_instruction(&program_id, &accounts, &ix.try_to_vec().unwrap())
+    /// };
+    /// let no_change = ProgramError::from(AccountDemoError::NoChange);
+    /// assert_eq!(update("Hello Solana!").unwrap_err(), no_change);
+    ///
+    /// // A different message gets past the check (this harness has no clock to go further)
+    /// assert_ne!(update("Hello again!").unwrap_err(), no_change);
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The account owner
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The program Config PDA
+    UpdateMessageIfChanged { message: String },
+    
+    /// Return the owner's UserData PDA (32 bytes) followed by its bump
+    /// (1 byte) via return data
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The owner, need not sign
+    GetPda,
+    
+    /// Like UpdateMessage, but the message must carry an ed25519 signature
+    /// by `signer_pubkey`, checked by an ed25519 program instruction placed
+    /// immediately before this one. `signer_pubkey` must be the owner or
+    /// the attester set with SetAttester. Marks the account as verified; a
+    /// plain update clears the mark again
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signer};
+    /// use solana_clean_demo::{attest, entrypoint::process_instruction, error::AccountDemoError, instruction::AccountDemoInstruction, pda};
+    /// use solana_clean_demo::state::{Config, UserData};
+    /// use solana_program::{account_info::AccountInfo, clock::Clock, ed25519_program, program_error::ProgramError, program_stubs, pubkey::Pubkey};
+    /// use solana_program::sysvar::{self, instructions::{self, BorrowedInstruction}};
+    ///
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock { slot: 100, ..Clock::default() } };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// let keypair = |seed| {
+    ///     let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    ///     Keypair { public: PublicKey::from(&secret), secret }
+    /// };
+    /// let (owner, attester, stranger) = (keypair(1), keypair(2), keypair(3));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let owner_key = Pubkey::new_from_array(owner.public.to_bytes());
+    /// let (data_key, system_key) = (Pubkey::new_unique(), solana_program::system_program::id());
+    /// let (sysvar_key, sysvar_owner, ed25519_key) = (instructions::id(), sysvar::id(), ed25519_program::id());
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let config = Config { is_initialized: true, admin: [7; 32], paused: false, attester: attester.public.to_bytes() };
+    ///
+    /// // Have `signer` sign the message, and run the update right after the
+    /// // ed25519 instruction checking that signature. With `spoofed`, a
+    /// // look-alike config trusting `signer` goes at index 3 and the real
+    /// // config is appended after it
+    /// let run_with = |signer: &Keypair, spoofed: bool| {
+    ///     let message = "Attested".to_string();
+    ///     let signature = signer.sign(message.as_bytes()).to_bytes();
+    ///     let signer_pubkey = signer.public.to_bytes();
+    ///     let verify_data = attest::ed25519_instruction_data(&signer_pubkey, &signature, message.as_bytes());
+    ///     let ix = AccountDemoInstruction::SignedUpdateMessage { message, signature, signer_pubkey }.try_to_vec().unwrap();
+    ///     let mut sysvar_data = instructions::construct_instructions_data(&[
+    ///         BorrowedInstruction { program_id: &ed25519_key, accounts: vec![], data: &verify_data },
+    ///         BorrowedInstruction { program_id: &program_id, accounts: vec![], data: &ix },
+    ///     ]);
+    ///     instructions::store_current_index(&mut sysvar_data, 1);
+    ///
+    ///     let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello".to_string()).unwrap();
+    ///     let mut data = vec![0u8; user_data.size_of_current() + 16];
+    ///     user_data.save(&mut data).unwrap();
+    ///
+    ///     let spoof = Config { attester: signer_pubkey, ..config.clone() };
+    ///     let spoof_key = Pubkey::new_unique();
+    ///     let (mut owner_lamports, mut data_lamports, mut sysvar_lamports, mut config_lamports, mut spoof_lamports) = (0, 0, 0, 0, 0);
+    ///     let (mut owner_data, mut config_data, mut spoof_data) = (vec![], config.try_to_vec().unwrap(), spoof.try_to_vec().unwrap());
+    ///     let mut accounts = vec![
+    ///         AccountInfo::new(&owner_key, true, false, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///         AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data, &program_id, false, 0),
+    ///         AccountInfo::new(&sysvar_key, false, false, &mut sysvar_lamports, &mut sysvar_data, &sysvar_owner, false, 0),
+    ///     ];
+    ///     if spoofed {
+    ///         accounts.push(AccountInfo::new(&spoof_key, false, false, &mut spoof_lamports, &mut spoof_data, &program_id, false, 0));
+    ///     }
+    ///     accounts.push(AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0));
+    ///     process_instruction(&program_id, &accounts, &ix)
+    /// };
+    /// let run = |signer: &Keypair| run_with(signer, false);
+    ///
+    /// run(&owner).unwrap();
+    /// run(&attester).unwrap();
+    ///
+    /// // A valid signature by any other key vouches for nothing
+    /// assert_eq!(run(&stranger).unwrap_err(), ProgramError::from(AccountDemoError::InvalidAttestation));
+    ///
+    /// // Nor does a config that isn't the program's Config PDA
+    /// assert_eq!(run_with(&stranger, true).unwrap_err(), ProgramError::InvalidArgument);
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The account owner
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The instructions sysvar
+    /// 3. `[]` The program Config PDA
+    SignedUpdateMessage { message: String, signature: [u8; 64], signer_pubkey: [u8; 32] },
+    
+    /// Move `amount` lamports from a UserData account to its owner, as long
+    /// as the account stays rent exempt at its current size. The account
+    /// must have a verified bump, see CanonicalizePda
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, program_stubs, pubkey::Pubkey, rent::Rent};
+    ///
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let owner_key = Pubkey::new_unique();
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// // Withdraw 1000 lamports from an account at the PDA for `bump`
+    /// let withdraw = |bump: u8| {
+    ///     let data_key = Pubkey::create_program_address(&pda::signer_seeds(&owner_key, &[bump]), &program_id).unwrap();
+    ///     let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    ///     user_data.set_canonical(bump);
+    ///     let mut data_data = vec![0u8; user_data.size_of_current()];
+    ///     user_data.save(&mut data_data).unwrap();
+    ///
+    ///     let data_lamports_start = Rent::default().minimum_balance(data_data.len()) + 1000;
+    ///     let (mut owner_lamports, mut data_lamports, mut config_lamports) = (0, data_lamports_start, 0);
+    ///     let (mut owner_data, mut config_data) = (vec![], vec![]);
+    ///     let accounts = [
+    ///         AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///         AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///         AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    ///     ];
+    ///     let ix = AccountDemoInstruction::WithdrawLamports { amount: 1000 }.try_to_vec().unwrap();
+    ///     process_instruction(&program_id, &accounts, &ix)
+    /// };
+    ///
+    /// let (_, bump) = pda::find_user_data_address(&owner_key, &program_id);
+    /// withdraw(bump).unwrap();
+    ///
+    /// // Another bump that derives a valid PDA from the same seeds is refused
+    /// let non_canonical = (0..bump)
+    ///     .rev()
+    ///     .find(|candidate| Pubkey::create_program_address(&pda::signer_seeds(&owner_key, &[*candidate]), &program_id).is_ok())
+    ///     .unwrap();
+    /// assert_eq!(withdraw(non_canonical).unwrap_err(), ProgramError::from(AccountDemoError::InvalidPda));
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner, receives the lamports
+    /// 1. `[writable]` The UserData account to withdraw from
+    /// 2. `[]` The program Config PDA
+    WithdrawLamports { amount: u64 },
+    
+    /// Update the message like UpdateMessage, then shrink the account to
+    /// the exact size of its data and refund the freed rent to the owner
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use std::convert::TryInto;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{clock::Clock, entrypoint::{deserialize, MAX_PERMITTED_DATA_INCREASE}, program_stubs, pubkey::Pubkey, rent::Rent};
+    ///
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock { slot: 100, ..Clock::default() } };
+    ///         0
+    ///     }
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// // Lay the accounts out the way the runtime serializes them, so the
+    /// // account data can be resized in place
+    /// fn serialize(accounts: &[(Pubkey, bool, bool, u64, Vec<u8>, Pubkey)]) -> Vec<u64> {
+    ///     let mut input = (accounts.len() as u64).to_le_bytes().to_vec();
+    ///     for (key, is_signer, is_writable, lamports, data, owner) in accounts {
+    ///         input.extend_from_slice(&[0xff, *is_signer as u8, *is_writable as u8, 0, 0, 0, 0, 0]);
+    ///         input.extend_from_slice(key.as_ref());
+    ///         input.extend_from_slice(owner.as_ref());
+    ///         input.extend_from_slice(&lamports.to_le_bytes());
+    ///         input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    ///         input.extend_from_slice(data);
+    ///         input.resize((input.len() + MAX_PERMITTED_DATA_INCREASE + 7) / 8 * 8, 0);
+    ///         input.extend_from_slice(&0u64.to_le_bytes()); // rent epoch
+    ///     }
+    ///     input.extend_from_slice(&[0u8; 8 + 32]); // no instruction data, program id
+    ///     input.chunks(8).map(|word| u64::from_ne_bytes(word.try_into().unwrap())).collect()
+    /// }
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "x".repeat(200)).unwrap();
+    /// let long_size = user_data.size_of_current();
+    /// let mut long_data = vec![0u8; long_size];
+    /// user_data.save(&mut long_data).unwrap();
+    /// let rent = Rent::default();
+    ///
+    /// let mut input = serialize(&[
+    ///     (owner_key, true, true, 0, vec![], system_key),
+    ///     (data_key, false, true, rent.minimum_balance(long_size), long_data, program_id),
+    ///     (system_key, false, false, 0, vec![], Pubkey::default()),
+    ///     (config_key, false, false, 0, vec![], program_id),
+    /// ]);
+    /// let (_, accounts, _) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+    ///
+    /// let compact = AccountDemoInstruction::UpdateMessageCompact { message: "Hi".to_string() };
+    /// process_instruction(&program_id, &accounts, &compact.try_to_vec().unwrap()).unwrap();
+    ///
+    /// let short_size = UserData::load_initialized(&accounts[1].data.borrow()).unwrap().size_of_current();
+    /// assert_eq!(accounts[1].data_len(), short_size);
+    /// assert_eq!(accounts[1].lamports(), rent.minimum_balance(short_size));
+    /// assert_eq!(accounts[0].lamports(), rent.minimum_balance(long_size) - rent.minimum_balance(short_size));
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner, receives the freed rent
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    UpdateMessageCompact { message: String },
+    
+    /// Copy the source account's message into the destination, bumping the
+    /// destination's update count. The source is left unchanged.
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, clock::Clock, program_stubs, pubkey::Pubkey};
+    ///
+    /// struct FixedClock;
+    /// impl program_stubs::SyscallStubs for FixedClock {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock { unix_timestamp: 1_700_000_000, ..Clock::default() } };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(FixedClock));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, source_key, destination_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// let mut source = UserData::new(owner_key, "Template".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut source_data = vec![0u8; source.size_of_current()];
+    /// source.save(&mut source_data).unwrap();
+    /// let mut destination = UserData::new(owner_key, "Copy".to_string(), "x".repeat(100)).unwrap();
+    /// let mut destination_data = vec![0u8; destination.size_of_current()];
+    /// destination.save(&mut destination_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut source_lamports, mut destination_lamports) = (0, 1_000_000, 1_000_000);
+    /// let (mut system_lamports, mut config_lamports) = (0, 0);
+    /// let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&source_key, false, false, &mut source_lamports, &mut source_data, &program_id, false, 0),
+    ///     AccountInfo::new(&destination_key, false, true, &mut destination_lamports, &mut destination_data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    ///
+    /// let copy = AccountDemoInstruction::CopyMessage.try_to_vec().unwrap();
+    /// process_instruction(&program_id, &accounts, &copy).unwrap();
+    ///
+    /// let copied = UserData::load_initialized(&accounts[2].data.borrow()).unwrap();
+    /// assert_eq!(copied.message, "Hello Solana!");
+    /// assert_eq!(copied.name, "Copy");
+    /// assert_eq!(copied.update_count, destination.update_count + 1);
+    /// // Only the message changed, so only its timestamp moves
+    /// assert_eq!((copied.name_updated_at, copied.message_updated_at), (destination.name_updated_at, 1_700_000_000));
+    /// let unchanged = UserData::load_initialized(&accounts[1].data.borrow()).unwrap();
+    /// assert_eq!((unchanged.message.as_str(), unchanged.update_count), ("Hello Solana!", source.update_count));
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The owner of both accounts, pays for any growth
+    /// 1. `[]` The source UserData account
+    /// 2. `[writable]` The destination UserData account
+    /// 3. `[]` The system program
+    /// 4. `[]` The program Config PDA
+    CopyMessage,
+    
+    /// Set the hash of the owner's off-chain avatar, or clear it with `None`
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// // Leave room for the hash so setting it needs no realloc
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data_data = vec![0u8; user_data.size_of_current() + 32];
+    /// user_data.save(&mut data_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 1_000_000, 0, 0);
+    /// let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let set_avatar_hash = |hash: Option<[u8; 32]>| {
+    ///     let ix = AccountDemoInstruction::SetAvatarHash { hash }.try_to_vec().unwrap();
+    ///     process_instruction(&program_id, &accounts, &ix).unwrap();
+    ///     UserData::load_initialized(&accounts[1].data.borrow()).unwrap().avatar_hash
+    /// };
+    ///
+    /// assert_eq!(set_avatar_hash(Some([0xab; 32])), Some([0xab; 32]));
+    /// assert_eq!(set_avatar_hash(None), None);
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner, pays for any growth
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    SetAvatarHash { hash: Option<[u8; 32]> },
+    
+    /// Initialize a new UserData account from a name and message packed
+    /// into one buffer, see `pack_name_and_message`. Saves the second
+    /// length prefix of Initialize
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError, pda, state::UserData};
+    /// use solana_clean_demo::instruction::{pack_name_and_message, AccountDemoInstruction};
+    /// use solana_program::{account_info::AccountInfo, clock::Clock, program_error::ProgramError, program_stubs, pubkey::Pubkey, rent::Rent};
+    ///
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Clock) = Clock { unix_timestamp: 1_700_000_000, ..Clock::default() } };
+    ///         0
+    ///     }
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let system_key = solana_program::system_program::id();
+    /// let owner_key = Pubkey::new_unique();
+    /// let (data_key, _) = pda::find_user_data_address(&owner_key, &program_id);
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 1_000_000, 0, 0);
+    /// let (mut owner_data, mut data_data, mut system_data, mut config_data) = (vec![], vec![0u8; 128], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let initialize_raw = |packed: Vec<u8>| {
+    ///     let ix = AccountDemoInstruction::InitializeRaw { packed }.try_to_vec().unwrap();
+    ///     process_instruction(&program_id, &accounts, &ix)
+    /// };
+    ///
+    /// // A name length past the end of the buffer is rejected
+    /// let malformed = ProgramError::from(AccountDemoError::InvalidInstructionData);
+    /// assert_eq!(initialize_raw(vec![9, 0, b'J', b'o']), Err(malformed));
+    ///
+    /// initialize_raw(pack_name_and_message("John Doe", "Hello Solana!")).unwrap();
+    /// let user_data = UserData::load_initialized(&accounts[1].data.borrow()).unwrap();
+    /// assert_eq!((user_data.name.as_str(), user_data.message.as_str()), ("John Doe", "Hello Solana!"));
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner
+    /// 1. `[writable]` The UserData account to initialize
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    InitializeRaw { packed: Vec<u8> },
+    
+    /// Set the free-form display label. Unlike the name it has no on-chain
+    /// meaning, so clients may use it however they like
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// // Leave room for the label so setting it needs no realloc
+    /// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data_data = vec![0u8; user_data.size_of_current() + UserData::MAX_LABEL_LENGTH];
+    /// user_data.save(&mut data_data).unwrap();
+    ///
+    /// let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 1_000_000, 0, 0);
+    /// let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    /// let accounts = [
+    ///     AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///     AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///     AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    /// ];
+    /// let run = |instruction: AccountDemoInstruction| {
+    ///     process_instruction(&program_id, &accounts, &instruction.try_to_vec().unwrap()).unwrap();
+    ///     UserData::load_initialized(&accounts[1].data.borrow()).unwrap().label
+    /// };
+    ///
+    /// assert_eq!(run(AccountDemoInstruction::SetLabel { label: "Work account".to_string() }), "Work account");
+    /// assert_eq!(run(AccountDemoInstruction::ClearLabel), "");
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner, pays for any growth
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    SetLabel { label: String },
+    
+    /// Clear the display label
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner
+    /// 1. `[writable]` The UserData account to update
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    ClearLabel,
+    
+    /// Check the account data against its stored checksum without changing
+    /// it, logging `Integrity: OK` or `Integrity: CORRUPT`. Corrupt data
+    /// fails with `ChecksumMismatch`, or `DataTypeMismatch` if it doesn't
+    /// decode at all
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, state::UserData};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let data_key = Pubkey::new_unique();
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data).unwrap();
+    ///
+    /// let verify = AccountDemoInstruction::VerifyIntegrity.try_to_vec().unwrap();
+    /// let run = |mut data: Vec<u8>| {
+    ///     let mut lamports = 1_000_000;
+    ///     let account = AccountInfo::new(&data_key, false, false, &mut lamports, &mut data, &program_id, false, 0);
+    ///     process_instruction(&program_id, &[account], &verify)
+    /// };
+    ///
+    /// assert_eq!(run(data.clone()), Ok(()));
+    ///
+    /// // Flip a byte of the name behind the program's back
+    /// data[38] ^= 0x20;
+    /// assert_eq!(run(data), Err(ProgramError::from(AccountDemoError::ChecksumMismatch)));
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[]` The UserData account to check
+    VerifyIntegrity,
+    
+    /// Verify that an account created before bumps were stored is its
+    /// owner's canonical PDA, then store the bump and set
+    /// `FLAG_CANONICAL`, so later checks can read the flag instead of
+    /// re-deriving the address. Any other address fails with `InvalidPda`.
+    /// Running it again on a canonical account changes nothing
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, pda};
+    /// use solana_clean_demo::state::{StoredUserData, UserData, UserDataV5};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let owner_key = Pubkey::new_unique();
+    /// let (pda_key, bump) = pda::find_user_data_address(&owner_key, &program_id);
+    /// let system_key = solana_program::system_program::id();
+    /// let canonicalize = AccountDemoInstruction::CanonicalizePda.try_to_vec().unwrap();
+    ///
+    /// // An account written before the bump was stored, with room to grow
+    /// let user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let legacy = StoredUserData::V5(UserDataV5 {
+    ///     flags: user_data.flags,
+    ///     owner: user_data.owner,
+    ///     name: user_data.name,
+    ///     message: user_data.message,
+    ///     update_count: user_data.update_count,
+    ///     messages: user_data.messages,
+    ///     alias: user_data.alias,
+    ///     last_update_slot: user_data.last_update_slot,
+    ///     checksum: user_data.checksum,
+    ///     avatar_hash: user_data.avatar_hash,
+    ///     label: user_data.label,
+    ///     name_updated_at: user_data.name_updated_at,
+    ///     message_updated_at: user_data.message_updated_at,
+    /// })
+    /// .try_to_vec()
+    /// .unwrap();
+    ///
+    /// let run = |data_key: &Pubkey| {
+    ///     let mut data_data = legacy.clone();
+    ///     data_data.resize(legacy.len() + 8, 0);
+    ///     let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 1_000_000, 0, 0);
+    ///     let (mut owner_data, mut system_data, mut config_data) = (vec![], vec![], vec![]);
+    ///     let accounts = [
+    ///         AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+    ///         AccountInfo::new(data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+    ///         AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///         AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    ///     ];
+    ///     process_instruction(&program_id, &accounts, &canonicalize)?;
+    ///     let user_data = UserData::load_initialized(&accounts[1].data.borrow()).unwrap();
+    ///     Ok::<_, ProgramError>(user_data)
+    /// };
+    ///
+    /// let user_data = run(&pda_key).unwrap();
+    /// assert!(user_data.is_canonical());
+    /// assert_eq!(user_data.bump, bump);
+    ///
+    /// // An account at any other address is not the owner's PDA
+    /// assert_eq!(run(&Pubkey::new_unique()).unwrap_err(), ProgramError::from(AccountDemoError::InvalidPda));
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The account owner, pays for any growth
+    /// 1. `[writable]` The UserData account to verify
+    /// 2. `[]` The system program
+    /// 3. `[]` The program Config PDA
+    CanonicalizePda,
+    
+    /// Create the program Config PDA and store `admin` as the key allowed
+    /// to change it. Only the program's upgrade authority may call this,
+    /// and only once
+    /// 
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError, instruction::AccountDemoInstruction, pda, state::Config};
+    /// use solana_program::{account_info::AccountInfo, bpf_loader_upgradeable, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (authority_key, admin_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let program_data_key = pda::find_program_data_address(&program_id);
+    /// let (loader_key, system_key) = (bpf_loader_upgradeable::id(), solana_program::system_program::id());
+    ///
+    /// // ProgramData state: its enum tag, the deploy slot, then the upgrade authority
+    /// let mut program_data_bytes = vec![3, 0, 0, 0];
+    /// program_data_bytes.extend_from_slice(&0u64.to_le_bytes());
+    /// program_data_bytes.push(1);
+    /// program_data_bytes.extend_from_slice(authority_key.as_ref());
+    ///
+    /// let run = |signer: &Pubkey, config_data: &mut Vec<u8>| {
+    ///     let (mut signer_lamports, mut config_lamports, mut program_data_lamports, mut system_lamports) = (0, 0, 0, 0);
+    ///     let (mut signer_data, mut program_data_data, mut system_data) = (vec![], program_data_bytes.clone(), vec![]);
+    ///     let accounts = [
+    ///         AccountInfo::new(signer, true, true, &mut signer_lamports, &mut signer_data, &system_key, false, 0),
+    ///         AccountInfo::new(&config_key, false, true, &mut config_lamports, config_data, &program_id, false, 0),
+    ///         AccountInfo::new(&program_data_key, false, false, &mut program_data_lamports, &mut program_data_data, &loader_key, false, 0),
+    ///         AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+    ///     ];
+    ///     let ix = AccountDemoInstruction::InitConfig { admin: admin_key.to_bytes() }.try_to_vec().unwrap();
+    ///     process_instruction(&program_id, &accounts, &ix)
+    /// };
+    ///
+    /// // Space the system program would have allocated for the PDA
+    /// let mut config_data = vec![0u8; Config::LEN];
+    ///
+    /// // Any other signer is turned away
+    /// assert_eq!(
+    ///     run(&Pubkey::new_unique(), &mut config_data).unwrap_err(),
+    ///     ProgramError::from(AccountDemoError::NotOwner)
+    /// );
+    ///
+    /// run(&authority_key, &mut config_data).unwrap();
+    /// let config = Config::load_initialized(&config_data).unwrap();
+    /// assert_eq!(config.admin, admin_key.to_bytes());
+    /// assert!(!config.paused);
+    ///
+    /// // The admin can't be replaced by running it again
+    /// assert_eq!(run(&authority_key, &mut config_data).unwrap_err(), ProgramError::AccountAlreadyInitialized);
+    /// ```
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer, writable]` The program's upgrade authority, pays for the Config PDA
+    /// 1. `[writable]` The program Config PDA
+    /// 2. `[]` The program's ProgramData account
+    /// 3. `[]` The system program
+    InitConfig { admin: [u8; 32] },
+    
+    /// Trust `attester` to sign messages for SignedUpdateMessage on behalf
+    /// of any owner. An all-zero key removes the attester
+    /// 
+    /// Accounts expected:
+    /// 0. `[signer]` The admin stored in the Config PDA by InitConfig
+    /// 1. `[writable]` The program Config PDA
+    SetAttester { attester: [u8; 32] },
 }
 
 impl AccountDemoInstruction {
+    /// Name of the variant encoded by a leading Borsh tag byte, if known
+    ///
+    /// ```
+    /// use solana_clean_demo::instruction::AccountDemoInstruction;
+    ///
+    /// assert_eq!(AccountDemoInstruction::variant_name(1), Some("UpdateMessage"));
+    /// assert_eq!(AccountDemoInstruction::variant_name(200), None);
+    /// ```
+    pub fn variant_name(tag: u8) -> Option<&'static str> {
+        match tag {
+            0 => Some("Initialize"),
+            1 => Some("UpdateMessage"),
+            2 => Some("SetMessageAt"),
+            3 => Some("GetMessageAt"),
+            4 => Some("SetAlias"),
+            5 => Some("CloseAccount"),
+            6 => Some("MergeInto"),
+            7 => Some("GetSummary"),
+            8 => Some("VerifyTag"),
+            9 => Some("SwapMessage"),
+            10 => Some("TopUpRent"),
+            11 => Some("InitializeMany"),
+            12 => Some("SetPaused"),
+            13 => Some("CheckOwner"),
+            14 => Some("SetFlags"),
+            15 => Some("InitializeWithReserve"),
+            #[cfg(feature = "debug-tools")]
+            16 => Some("DumpRaw"),
+            17 => Some("UpdateMessageIfChanged"),
+            18 => Some("GetPda"),
+            19 => Some("SignedUpdateMessage"),
+            20 => Some("WithdrawLamports"),
+            21 => Some("UpdateMessageCompact"),
+            22 => Some("CopyMessage"),
+            23 => Some("SetAvatarHash"),
+            24 => Some("InitializeRaw"),
+            25 => Some("SetLabel"),
+            26 => Some("ClearLabel"),
+            27 => Some("VerifyIntegrity"),
+            28 => Some("CanonicalizePda"),
+            29 => Some("InitConfig"),
+            30 => Some("SetAttester"),
+            _ => None,
+        }
+    }
+
+    /// Whether the instruction writes state and is blocked while paused
+    ///
+    /// Every such instruction takes the program Config PDA as its last account.
+    ///
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{entrypoint::process_instruction, error::AccountDemoError};
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, pda, state::Config};
+    /// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let program_id = Pubkey::new_unique();
+    /// let (config_key, _) = pda::find_config_address(&program_id);
+    /// let (admin_key, owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// // A config InitConfig created with `admin_key` as the admin
+    /// let created = Config { is_initialized: true, admin: admin_key.to_bytes(), paused: false, attester: [0; 32] };
+    ///
+    /// let (mut admin_lamports, mut config_lamports, mut owner_lamports, mut data_lamports) = (0, 0, 0, 0);
+    /// let (mut admin_data, mut config_data, mut owner_data, mut data_data) =
+    ///     (vec![], created.try_to_vec().unwrap(), vec![], vec![]);
+    /// let admin = AccountInfo::new(&admin_key, true, false, &mut admin_lamports, &mut admin_data, &system_key, false, 0);
+    /// let config = AccountInfo::new(&config_key, false, true, &mut config_lamports, &mut config_data, &program_id, false, 0);
+    /// let owner = AccountInfo::new(&owner_key, true, false, &mut owner_lamports, &mut owner_data, &system_key, false, 0);
+    /// let data = AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0);
+    ///
+    /// let set_paused = |paused| {
+    ///     let ix = AccountDemoInstruction::SetPaused { paused }.try_to_vec().unwrap();
+    ///     process_instruction(&program_id, &[admin.clone(), config.clone()], &ix)
+    /// };
+    /// let update = AccountDemoInstruction::UpdateMessage { message: "hi".to_string() }.try_to_vec().unwrap();
+    /// let update_accounts = [owner.clone(), data.clone(), config.clone()];
+    /// let paused_err = ProgramError::from(AccountDemoError::ProgramPaused);
+    ///
+    /// set_paused(true).unwrap();
+    /// assert_eq!(process_instruction(&program_id, &update_accounts, &update).unwrap_err(), paused_err);
+    ///
+    /// // Once resumed the update gets past the pause check (and fails on the empty account instead)
+    /// set_paused(false).unwrap();
+    /// assert_ne!(process_instruction(&program_id, &update_accounts, &update).unwrap_err(), paused_err);
+    ///
+    /// // Only the stored admin may toggle the pause
+    /// let mut other_data = vec![];
+    /// let (other_key, mut other_lamports) = (Pubkey::new_unique(), 0);
+    /// let other = AccountInfo::new(&other_key, true, false, &mut other_lamports, &mut other_data, &system_key, false, 0);
+    /// let ix = AccountDemoInstruction::SetPaused { paused: true }.try_to_vec().unwrap();
+    /// assert_eq!(
+    ///     process_instruction(&program_id, &[other, config.clone()], &ix).unwrap_err(),
+    ///     ProgramError::from(AccountDemoError::NotOwner)
+    /// );
+    /// ```
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            AccountDemoInstruction::GetMessageAt { .. }
+                | AccountDemoInstruction::GetSummary
+                | AccountDemoInstruction::VerifyTag { .. }
+                | AccountDemoInstruction::SetPaused { .. }
+                | AccountDemoInstruction::InitConfig { .. }
+                | AccountDemoInstruction::SetAttester { .. }
+                | AccountDemoInstruction::CheckOwner
+                | AccountDemoInstruction::DumpRaw
+                | AccountDemoInstruction::GetPda
+                | AccountDemoInstruction::VerifyIntegrity
+        )
+    }
+
+    /// The accounts this instruction expects, in order
+    ///
+    /// The builders derive their account metas from this, so it always
+    /// matches what they produce.
+    ///
+    /// ```
+    /// use solana_clean_demo::instruction::{AccountDemoInstruction, AccountSpec};
+    ///
+    /// let initialize = AccountDemoInstruction::Initialize { name: "John Doe".to_string(), message: "Hi".to_string() };
+    /// let flags: Vec<_> = initialize
+    ///     .required_accounts()
+    ///     .iter()
+    ///     .map(|spec: &AccountSpec| (spec.name, spec.is_signer, spec.is_writable))
+    ///     .collect();
+    /// assert_eq!(
+    ///     flags,
+    ///     [
+    ///         ("owner", true, true),
+    ///         ("user data", false, true),
+    ///         ("system program", false, false),
+    ///         ("config", false, false),
+    ///     ]
+    /// );
+    ///
+    /// // InitializeMany takes an owner and a UserData account per entry
+    /// let entries = vec![("a".to_string(), "b".to_string()); 3];
+    /// assert_eq!(AccountDemoInstruction::InitializeMany { entries }.required_accounts().len(), 1 + 3 * 2 + 1);
+    /// ```
+    pub fn required_accounts(&self) -> Vec<AccountSpec> {
+        match self {
+            AccountDemoInstruction::InitializeMany { entries } => {
+                let mut specs = vec![SYSTEM_PROGRAM];
+                for _ in entries {
+                    specs.extend([OWNER, USER_DATA]);
+                }
+                specs.push(CONFIG);
+                specs
+            }
+            _ => self.fixed_accounts().to_vec(),
+        }
+    }
+
+    /// The length of `required_accounts`, without allocating
+    ///
+    /// ```
+    /// use solana_clean_demo::instruction::AccountDemoInstruction;
+    ///
+    /// let entries = vec![("a".to_string(), "b".to_string()); 3];
+    /// for instruction in [
+    ///     AccountDemoInstruction::InitializeMany { entries },
+    ///     AccountDemoInstruction::MergeInto,
+    ///     AccountDemoInstruction::GetSummary,
+    /// ] {
+    ///     assert_eq!(instruction.required_account_count(), instruction.required_accounts().len());
+    /// }
+    /// ```
+    pub fn required_account_count(&self) -> usize {
+        let per_entry = match self {
+            AccountDemoInstruction::InitializeMany { entries } => 2 * entries.len(),
+            _ => 0,
+        };
+        self.fixed_accounts().len() + per_entry
+    }
+
+    // The accounts that don't depend on the instruction data; InitializeMany
+    // also takes an owner and a UserData account per entry
+    fn fixed_accounts(&self) -> &'static [AccountSpec] {
+        match self {
+            AccountDemoInstruction::Initialize { .. }
+            | AccountDemoInstruction::SetMessageAt { .. }
+            | AccountDemoInstruction::SetAlias { .. }
+            | AccountDemoInstruction::SwapMessage { .. }
+            | AccountDemoInstruction::InitializeWithReserve { .. }
+            | AccountDemoInstruction::UpdateMessageCompact { .. }
+            | AccountDemoInstruction::SetAvatarHash { .. }
+            | AccountDemoInstruction::InitializeRaw { .. }
+            | AccountDemoInstruction::SetLabel { .. }
+            | AccountDemoInstruction::ClearLabel
+            | AccountDemoInstruction::CanonicalizePda => &[OWNER, USER_DATA, SYSTEM_PROGRAM, CONFIG],
+            AccountDemoInstruction::UpdateMessage { .. }
+            | AccountDemoInstruction::SetFlags { .. }
+            | AccountDemoInstruction::UpdateMessageIfChanged { .. } => &[OWNER_READONLY, USER_DATA, CONFIG],
+            AccountDemoInstruction::GetMessageAt { .. }
+            | AccountDemoInstruction::GetSummary
+            | AccountDemoInstruction::VerifyTag { .. }
+            | AccountDemoInstruction::VerifyIntegrity => &[USER_DATA_READONLY],
+            AccountDemoInstruction::CloseAccount
+            | AccountDemoInstruction::WithdrawLamports { .. } => &[OWNER, USER_DATA, CONFIG],
+            AccountDemoInstruction::MergeInto => &[OWNER, SOURCE, DESTINATION, SYSTEM_PROGRAM, CONFIG],
+            AccountDemoInstruction::CopyMessage => {
+                &[OWNER, SOURCE_READONLY, DESTINATION, SYSTEM_PROGRAM, CONFIG]
+            }
+            AccountDemoInstruction::TopUpRent => &[PAYER, USER_DATA, SYSTEM_PROGRAM, CONFIG],
+            AccountDemoInstruction::InitializeMany { .. } => &[SYSTEM_PROGRAM, CONFIG],
+            AccountDemoInstruction::SetPaused { .. } | AccountDemoInstruction::SetAttester { .. } => {
+                &[ADMIN, CONFIG_WRITABLE]
+            }
+            AccountDemoInstruction::InitConfig { .. } => {
+                &[UPGRADE_AUTHORITY, CONFIG_WRITABLE, PROGRAM_DATA, SYSTEM_PROGRAM]
+            }
+            AccountDemoInstruction::CheckOwner => &[USER_DATA_READONLY, KEY],
+            AccountDemoInstruction::DumpRaw => &[ACCOUNT],
+            AccountDemoInstruction::GetPda => &[OWNER_UNSIGNED],
+            AccountDemoInstruction::SignedUpdateMessage { .. } => {
+                &[OWNER_READONLY, USER_DATA, INSTRUCTIONS_SYSVAR, CONFIG]
+            }
+        }
+    }
+
+    // Pair `keys` with the signer and writable flags from required_accounts
+    fn account_metas(&self, keys: &[Pubkey]) -> Vec<AccountMeta> {
+        let specs = self.required_accounts();
+        debug_assert_eq!(specs.len(), keys.len());
+        specs
+            .iter()
+            .zip(keys)
+            .map(|(spec, key)| AccountMeta {
+                pubkey: *key,
+                is_signer: spec.is_signer,
+                is_writable: spec.is_writable,
+            })
+            .collect()
+    }
+
+    /// Panics if the name or message is too long, see `try_initialize`
     pub fn initialize(
         program_id: &Pubkey,
         owner: &Pubkey,
@@ -29,37 +1828,777 @@ impl AccountDemoInstruction {
         name: String,
         message: String,
     ) -> Instruction {
-        let data = AccountDemoInstruction::Initialize { name, message };
-        let data = data.try_to_vec().unwrap();
+        Self::try_initialize(program_id, owner, user_data_account, name, message).unwrap()
+    }
+
+    /// Like `initialize`, but borrowing the name and message, so string
+    /// literals need no allocation at the call site
+    ///
+    /// ```
+    /// use solana_clean_demo::instruction::AccountDemoInstruction;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let (program_id, owner, data) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let ix = AccountDemoInstruction::initialize_str(&program_id, &owner, &data, "John Doe", "Hi");
+    /// let owned = AccountDemoInstruction::initialize(&program_id, &owner, &data, "John Doe".to_string(), "Hi".to_string());
+    /// assert_eq!(ix, owned);
+    /// ```
+    pub fn initialize_str(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        name: &str,
+        message: &str,
+    ) -> Instruction {
+        Self::initialize(program_id, owner, user_data_account, name.to_string(), message.to_string())
+    }
+
+    /// Build an Initialize instruction, rejecting a name or message over
+    /// the program's limits instead of panicking
+    ///
+    /// ```
+    /// use std::io;
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, state::UserData};
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let (program_id, owner, data) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let ix = AccountDemoInstruction::try_initialize(&program_id, &owner, &data, "John Doe".to_string(), "Hi".to_string()).unwrap();
+    /// assert_eq!(ix.accounts.len(), 4);
+    ///
+    /// let long_name = "n".repeat(UserData::MAX_NAME_LENGTH + 1);
+    /// let err = AccountDemoInstruction::try_initialize(&program_id, &owner, &data, long_name, "Hi".to_string()).unwrap_err();
+    /// assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn try_initialize(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        name: String,
+        message: String,
+    ) -> Result<Instruction, io::Error> {
+        check_length("name", &name, UserData::MAX_NAME_LENGTH)?;
+        check_length("message", &message, UserData::MAX_MESSAGE_LENGTH)?;
+
+        let instruction = AccountDemoInstruction::Initialize { name, message };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec()?,
+        })
+    }
+
+    /// Panics if the message is too long, see `try_update_message`
+    pub fn update_message(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        message: String,
+    ) -> Instruction {
+        Self::try_update_message(program_id, owner, user_data_account, message).unwrap()
+    }
+
+    /// Like `update_message`, but borrowing the message
+    ///
+    /// ```
+    /// use solana_clean_demo::instruction::AccountDemoInstruction;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let (program_id, owner, data) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let ix = AccountDemoInstruction::update_message_str(&program_id, &owner, &data, "Hi");
+    /// assert_eq!(ix, AccountDemoInstruction::update_message(&program_id, &owner, &data, "Hi".to_string()));
+    /// ```
+    pub fn update_message_str(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        message: &str,
+    ) -> Instruction {
+        Self::update_message(program_id, owner, user_data_account, message.to_string())
+    }
+
+    /// Build an UpdateMessage instruction, rejecting a message over the
+    /// program's limit instead of panicking
+    ///
+    /// ```
+    /// use std::io;
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, state::UserData};
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let (program_id, owner, data) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let ix = AccountDemoInstruction::try_update_message(&program_id, &owner, &data, "Hi".to_string()).unwrap();
+    /// assert_eq!(ix.accounts.len(), 3);
+    ///
+    /// let long_message = "m".repeat(UserData::MAX_MESSAGE_LENGTH + 1);
+    /// let err = AccountDemoInstruction::try_update_message(&program_id, &owner, &data, long_message).unwrap_err();
+    /// assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    /// ```
+    pub fn try_update_message(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        message: String,
+    ) -> Result<Instruction, io::Error> {
+        check_length("message", &message, UserData::MAX_MESSAGE_LENGTH)?;
+
+        let instruction = AccountDemoInstruction::UpdateMessage { message };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec()?,
+        })
+    }
+
+    pub fn set_message_at(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        index: u8,
+        message: String,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SetMessageAt { index, message };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
 
         Instruction {
             program_id: *program_id,
-            accounts: vec![
-                AccountMeta::new(*owner, true),
-                AccountMeta::new(*user_data_account, false),
-                AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
-                AccountMeta::new_readonly(solana_program::system_program::id(), false),
-            ],
-            data,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
         }
     }
 
-    pub fn update_message(
+    pub fn get_message_at(
+        program_id: &Pubkey,
+        user_data_account: &Pubkey,
+        index: u8,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::GetMessageAt { index };
+        let accounts = instruction.account_metas(&[
+            *user_data_account,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn set_alias(
         program_id: &Pubkey,
         owner: &Pubkey,
         user_data_account: &Pubkey,
+        alias: String,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SetAlias { alias };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn close_account(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::CloseAccount;
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn merge_into(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        source_account: &Pubkey,
+        destination_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::MergeInto;
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *source_account,
+            *destination_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn get_summary(
+        program_id: &Pubkey,
+        user_data_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::GetSummary;
+        let accounts = instruction.account_metas(&[
+            *user_data_account,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn verify_tag(
+        program_id: &Pubkey,
+        user_data_account: &Pubkey,
+        expected: Vec<u8>,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::VerifyTag { expected };
+        let accounts = instruction.account_metas(&[
+            *user_data_account,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn verify_integrity(
+        program_id: &Pubkey,
+        user_data_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::VerifyIntegrity;
+        let accounts = instruction.account_metas(&[
+            *user_data_account,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn swap_message(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        new: String,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SwapMessage { new };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn top_up_rent(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        user_data_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::TopUpRent;
+        let accounts = instruction.account_metas(&[
+            *payer,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    /// `accounts` pairs each owner with its UserData account, in entry order
+    pub fn initialize_many(
+        program_id: &Pubkey,
+        accounts: &[(Pubkey, Pubkey)],
+        entries: Vec<(String, String)>,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::InitializeMany { entries };
+        let mut keys = vec![solana_program::system_program::id()];
+        for (owner, user_data_account) in accounts {
+            keys.push(*owner);
+            keys.push(*user_data_account);
+        }
+        keys.push(pda::find_config_address(program_id).0);
+
+        Instruction {
+            program_id: *program_id,
+            accounts: instruction.account_metas(&keys),
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn set_paused(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        paused: bool,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SetPaused { paused };
+        let accounts = instruction.account_metas(&[
+            *admin,
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    /// Pass `Pubkey::default()` to remove the attester
+    pub fn set_attester(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        attester: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SetAttester { attester: attester.to_bytes() };
+        let accounts = instruction.account_metas(&[
+            *admin,
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn init_config(
+        program_id: &Pubkey,
+        upgrade_authority: &Pubkey,
+        admin: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::InitConfig { admin: admin.to_bytes() };
+        let accounts = instruction.account_metas(&[
+            *upgrade_authority,
+            pda::find_config_address(program_id).0,
+            pda::find_program_data_address(program_id),
+            solana_program::system_program::id(),
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn check_owner(
+        program_id: &Pubkey,
+        user_data_account: &Pubkey,
+        key: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::CheckOwner;
+        let accounts = instruction.account_metas(&[
+            *user_data_account,
+            *key,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn set_flags(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        mask: u8,
+        value: u8,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SetFlags { mask, value };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn initialize_with_reserve(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        name: String,
         message: String,
+        reserve_bytes: u32,
     ) -> Instruction {
-        let data = AccountDemoInstruction::UpdateMessage { message };
-        let data = data.try_to_vec().unwrap();
+        let instruction = AccountDemoInstruction::InitializeWithReserve { name, message, reserve_bytes };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
 
         Instruction {
             program_id: *program_id,
-            accounts: vec![
-                AccountMeta::new_readonly(*owner, true),
-                AccountMeta::new(*user_data_account, false),
-            ],
-            data,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
         }
     }
+
+    #[cfg(feature = "debug-tools")]
+    pub fn dump_raw(
+        program_id: &Pubkey,
+        account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::DumpRaw;
+        let accounts = instruction.account_metas(&[
+            *account,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn update_message_if_changed(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        message: String,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::UpdateMessageIfChanged { message };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn get_pda(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::GetPda;
+        let accounts = instruction.account_metas(&[
+            *owner,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    /// Pair the returned instruction with an ed25519 program instruction
+    /// over `message`, placed directly before it in the same transaction
+    pub fn signed_update_message(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        message: String,
+        signature: [u8; 64],
+        signer_pubkey: [u8; 32],
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SignedUpdateMessage { message, signature, signer_pubkey };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::sysvar::instructions::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn withdraw_lamports(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::WithdrawLamports { amount };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn update_message_compact(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        message: String,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::UpdateMessageCompact { message };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn copy_message(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        source_account: &Pubkey,
+        destination_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::CopyMessage;
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *source_account,
+            *destination_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn set_avatar_hash(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        hash: Option<[u8; 32]>,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SetAvatarHash { hash };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    /// Panics if the name is longer than `u16::MAX` bytes, see
+    /// `pack_name_and_message`
+    pub fn initialize_raw(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        name: &str,
+        message: &str,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::InitializeRaw { packed: pack_name_and_message(name, message) };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn set_label(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+        label: String,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::SetLabel { label };
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn clear_label(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::ClearLabel;
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    pub fn canonicalize_pda(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        user_data_account: &Pubkey,
+    ) -> Instruction {
+        let instruction = AccountDemoInstruction::CanonicalizePda;
+        let accounts = instruction.account_metas(&[
+            *owner,
+            *user_data_account,
+            solana_program::system_program::id(),
+            pda::find_config_address(program_id).0,
+        ]);
+
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+}
+
+/// Pack a name and message into the buffer InitializeRaw expects: the
+/// name's length as a little-endian `u16`, the name, then the message
+/// filling the rest
+///
+/// Panics if the name is longer than `u16::MAX` bytes.
+pub fn pack_name_and_message(name: &str, message: &str) -> Vec<u8> {
+    let name_len = u16::try_from(name.len()).expect("name longer than u16::MAX bytes");
+
+    let mut packed = Vec::with_capacity(2 + name.len() + message.len());
+    packed.extend_from_slice(&name_len.to_le_bytes());
+    packed.extend_from_slice(name.as_bytes());
+    packed.extend_from_slice(message.as_bytes());
+    packed
+}
+
+/// Split an InitializeRaw buffer back into its name and message
+///
+/// Fails with `InvalidInstructionData` if the buffer is too short for the
+/// length prefix or the name it announces, or if either field isn't UTF-8.
+///
+/// ```
+/// use solana_clean_demo::error::AccountDemoError;
+/// use solana_clean_demo::instruction::{pack_name_and_message, unpack_name_and_message};
+///
+/// let packed = pack_name_and_message("John Doe", "Hello Solana!");
+/// assert_eq!(unpack_name_and_message(&packed).unwrap(), ("John Doe".to_string(), "Hello Solana!".to_string()));
+///
+/// // Either field may be empty
+/// assert_eq!(unpack_name_and_message(&[0, 0]).unwrap(), (String::new(), String::new()));
+/// assert_eq!(unpack_name_and_message(&[2, 0, b'J', b'D']).unwrap(), ("JD".to_string(), String::new()));
+///
+/// // Missing or truncated prefix, a name running past the end, invalid UTF-8
+/// for malformed in [&[][..], &[1], &[3, 0, b'J', b'D'], &[0, 1, b'J'], &[1, 0, 0xff], &[0, 0, 0xc3]] {
+///     assert!(matches!(unpack_name_and_message(malformed), Err(AccountDemoError::InvalidInstructionData)));
+/// }
+/// ```
+pub fn unpack_name_and_message(packed: &[u8]) -> Result<(String, String), AccountDemoError> {
+    if packed.len() < 2 {
+        return Err(AccountDemoError::InvalidInstructionData);
+    }
+    let (prefix, rest) = packed.split_at(2);
+    let name_len = u16::from_le_bytes([prefix[0], prefix[1]]) as usize;
+    if name_len > rest.len() {
+        return Err(AccountDemoError::InvalidInstructionData);
+    }
+    let (name, message) = rest.split_at(name_len);
+
+    let to_string = |bytes: &[u8]| {
+        String::from_utf8(bytes.to_vec()).map_err(|_| AccountDemoError::InvalidInstructionData)
+    };
+    Ok((to_string(name)?, to_string(message)?))
+}
+
+fn check_length(field: &str, value: &str, max: usize) -> Result<(), io::Error> {
+    if value.len() > max {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is {} bytes, the maximum is {}", field, value.len(), max),
+        ));
+    }
+    Ok(())
 }
@@ -1,8 +1,51 @@
+// Crate-local msg! so the `no-logs` feature can compile every log away.
+// Declared before the modules so they pick it up by textual scope.
+#[cfg(not(feature = "no-logs"))]
+macro_rules! msg {
+    ($($arg:tt)*) => {
+        solana_program::msg!($($arg)*)
+    };
+}
+
+// Arguments stay type-checked but are never evaluated
+#[cfg(feature = "no-logs")]
+macro_rules! msg {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+pub mod attest;
+#[cfg(feature = "client")]
+pub mod client;
 pub mod entrypoint;
 pub mod error;
 pub mod instruction;
+pub mod pda;
 pub mod processor;
 pub mod state;
+pub mod tag;
+#[cfg(all(feature = "test-support", not(target_os = "solana")))]
+pub mod test_support;
+
+/// Compare two public keys in constant time
+///
+/// Used for every ownership check, so how long a check takes reveals
+/// nothing about how much of a key matched.
+///
+/// ```
+/// use solana_clean_demo::pubkeys_eq_ct;
+/// use solana_program::pubkey::Pubkey;
+///
+/// let key = Pubkey::new_unique();
+/// assert!(pubkeys_eq_ct(&key, &key.clone()));
+/// assert!(!pubkeys_eq_ct(&key, &Pubkey::new_unique()));
+/// ```
+pub fn pubkeys_eq_ct(a: &solana_program::pubkey::Pubkey, b: &solana_program::pubkey::Pubkey) -> bool {
+    constant_time_eq::constant_time_eq(a.as_ref(), b.as_ref())
+}
 
 // Export current sdk types for downstream users building with a different sdk version
 pub use solana_program;
@@ -0,0 +1,103 @@
+use solana_program::{bpf_loader_upgradeable, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::AccountDemoError;
+
+/// Seed prefix of every UserData PDA
+pub const USER_DATA_SEED: &[u8] = b"user-data";
+
+/// Seed of the program-wide Config PDA
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Derive the UserData PDA of `owner` and its canonical bump
+pub fn find_user_data_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USER_DATA_SEED, owner.as_ref()], program_id)
+}
+
+/// Check that `stored_bump` is the canonical bump of `owner`'s UserData PDA
+///
+/// Other bumps can also derive valid program addresses from the same
+/// seeds. Only the canonical one, the highest that works, names the
+/// account the program actually created, so anything else fails with
+/// `InvalidPda`.
+///
+/// ```
+/// use solana_clean_demo::{error::AccountDemoError, pda::{assert_canonical_bump, find_user_data_address, signer_seeds}};
+/// use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+///
+/// let program_id = Pubkey::new_unique();
+/// let owner = Pubkey::new_unique();
+/// let (_, bump) = find_user_data_address(&owner, &program_id);
+/// assert_eq!(assert_canonical_bump(&program_id, &owner, bump), Ok(()));
+///
+/// // A lower bump that still derives a valid PDA is rejected
+/// let non_canonical = (0..bump)
+///     .rev()
+///     .find(|candidate| Pubkey::create_program_address(&signer_seeds(&owner, &[*candidate]), &program_id).is_ok())
+///     .unwrap();
+/// assert_eq!(
+///     assert_canonical_bump(&program_id, &owner, non_canonical),
+///     Err(ProgramError::from(AccountDemoError::InvalidPda))
+/// );
+/// ```
+pub fn assert_canonical_bump(program_id: &Pubkey, owner: &Pubkey, stored_bump: u8) -> Result<(), ProgramError> {
+    let (_, bump) = find_user_data_address(owner, program_id);
+    if stored_bump != bump {
+        return Err(AccountDemoError::InvalidPda.into());
+    }
+    Ok(())
+}
+
+/// The UserData PDA of `owner` followed by its bump, as returned by GetPda
+///
+/// ```
+/// use solana_clean_demo::pda::{user_data_address_bytes, USER_DATA_SEED};
+/// use solana_program::pubkey::Pubkey;
+///
+/// let program_id = Pubkey::new_unique();
+/// let owner = Pubkey::new_unique();
+/// let (address, bump) = Pubkey::find_program_address(&[USER_DATA_SEED, owner.as_ref()], &program_id);
+///
+/// let bytes = user_data_address_bytes(&owner, &program_id);
+/// assert_eq!(&bytes[..32], address.as_ref());
+/// assert_eq!(bytes[32], bump);
+/// ```
+pub fn user_data_address_bytes(owner: &Pubkey, program_id: &Pubkey) -> [u8; 33] {
+    let (address, bump) = find_user_data_address(owner, program_id);
+    let mut bytes = [0u8; 33];
+    bytes[..32].copy_from_slice(address.as_ref());
+    bytes[32] = bump;
+    bytes
+}
+
+/// Seeds for signing CPIs as the UserData PDA of `owner`
+///
+/// ```
+/// use solana_clean_demo::pda::{find_user_data_address, signer_seeds};
+/// use solana_program::pubkey::Pubkey;
+///
+/// let program_id = Pubkey::new_unique();
+/// let owner = Pubkey::new_unique();
+/// let (address, bump) = find_user_data_address(&owner, &program_id);
+///
+/// let bump_seed = [bump];
+/// let seeds = signer_seeds(&owner, &bump_seed);
+/// assert_eq!(Pubkey::create_program_address(&seeds, &program_id).unwrap(), address);
+/// ```
+pub fn signer_seeds<'a>(owner: &'a Pubkey, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+    [USER_DATA_SEED, owner.as_ref(), bump]
+}
+
+/// Derive the program's Config PDA and its canonical bump
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/// Seeds for signing CPIs as the Config PDA
+pub fn config_signer_seeds(bump: &[u8; 1]) -> [&[u8]; 2] {
+    [CONFIG_SEED, bump]
+}
+
+/// Derive the ProgramData account holding the program's upgrade authority
+pub fn find_program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
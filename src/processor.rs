@@ -1,46 +1,303 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use constant_time_eq::constant_time_eq;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     entrypoint::ProgramResult,
-    msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
+    program_memory::sol_memset,
+    program_utils::limited_deserialize,
+    ed25519_program,
     pubkey::Pubkey,
     system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{clock::Clock, instructions, rent::Rent, Sysvar},
 };
 
 use crate::{
+    attest,
     error::AccountDemoError,
-    instruction::AccountDemoInstruction,
-    state::{UserData, StringPad},
+    instruction::{unpack_name_and_message, AccountDemoInstruction, MAX_BATCH_SIZE},
+    pda, pubkeys_eq_ct,
+    state::{write_account_data, Config, UserData},
+    tag,
 };
 
 pub struct Processor;
 
 impl Processor {
+    /// Decode and run one instruction
+    ///
+    /// With the `profile` feature, the remaining compute units are logged
+    /// before and after the handler runs, so the difference is what the
+    /// instruction cost.
+    ///
+    /// ```
+    /// use std::sync::Mutex;
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, processor::Processor};
+    /// use solana_program::{account_info::AccountInfo, program_stubs, pubkey::Pubkey};
+    ///
+    /// static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    /// struct CaptureLogs;
+    /// impl program_stubs::SyscallStubs for CaptureLogs {
+    ///     fn sol_log(&self, message: &str) {
+    ///         LOGS.lock().unwrap().push(message.to_string());
+    ///     }
+    ///     fn sol_log_compute_units(&self) {
+    ///         LOGS.lock().unwrap().push("compute units".to_string());
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(CaptureLogs));
+    ///
+    /// let (program_id, owner_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    /// let (mut lamports, mut data) = (0, vec![]);
+    /// let owner = AccountInfo::new(&owner_key, false, false, &mut lamports, &mut data, &owner_key, false, 0);
+    /// let get_pda = AccountDemoInstruction::GetPda.try_to_vec().unwrap();
+    /// Processor::process(&program_id, &[owner], &get_pda).unwrap();
+    ///
+    /// let logs = LOGS.lock().unwrap();
+    /// let profiled = |entry: &str| logs.iter().any(|log| log == entry);
+    /// assert_eq!(profiled("compute units"), cfg!(feature = "profile"));
+    /// if cfg!(all(feature = "profile", not(feature = "no-logs"))) {
+    ///     let start = logs.iter().position(|log| log == "Profile: GetPda start").unwrap();
+    ///     let end = logs.iter().position(|log| log == "Profile: GetPda end").unwrap();
+    ///     assert_eq!(logs[start + 1], "compute units");
+    ///     assert_eq!(logs[end + 1], "compute units");
+    /// }
+    /// ```
+    ///
+    /// A transaction that passes no accounts at all is rejected before any
+    /// handler runs, with a log saying so.
+    ///
+    /// ```
+    /// use std::sync::Mutex;
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{instruction::AccountDemoInstruction, processor::Processor};
+    /// use solana_program::{program_error::ProgramError, program_stubs, pubkey::Pubkey};
+    ///
+    /// static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    /// struct CaptureLogs;
+    /// impl program_stubs::SyscallStubs for CaptureLogs {
+    ///     fn sol_log(&self, message: &str) {
+    ///         LOGS.lock().unwrap().push(message.to_string());
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(CaptureLogs));
+    ///
+    /// let get_summary = AccountDemoInstruction::GetSummary.try_to_vec().unwrap();
+    /// let result = Processor::process(&Pubkey::new_unique(), &[], &get_summary);
+    /// assert_eq!(result, Err(ProgramError::NotEnoughAccountKeys));
+    ///
+    /// let logged = LOGS.lock().unwrap().iter().any(|log| log.starts_with("Error: No accounts provided"));
+    /// assert_eq!(logged, cfg!(not(feature = "no-logs")));
+    /// ```
     pub fn process(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         instruction_data: &[u8],
     ) -> ProgramResult {
-        msg!("Processing instruction with data: {:?}", instruction_data);
+        msg!("Processing instruction with data: {}", truncated_bytes(instruction_data, MAX_LOGGED_BYTES));
+        
+        // Name the variant up front so decoding errors say what was attempted
+        let tag = match instruction_data.first() {
+            Some(tag) => *tag,
+            None => {
+                msg!("Error: Empty instruction data");
+                return Err(AccountDemoError::InvalidInstructionData.into());
+            }
+        };
+        let variant = match AccountDemoInstruction::variant_name(tag) {
+            Some(variant) => variant,
+            None => {
+                msg!("Error: Unknown instruction variant {}", tag);
+                return Err(AccountDemoError::InvalidInstructionData.into());
+            }
+        };
+        
         let instruction = AccountDemoInstruction::try_from_slice(instruction_data)
             .map_err(|err| {
-                msg!("Failed to deserialize instruction: {:?}", err);
+                msg!("Failed to deserialize {} instruction: {:?}", variant, err);
                 AccountDemoError::InvalidInstructionData
             })?;
 
-        match instruction {
+        // Bound the batch so it stays within compute limits. Checked first
+        // so an oversized batch isn't reported as missing accounts
+        if let AccountDemoInstruction::InitializeMany { entries } = &instruction {
+            if entries.len() > MAX_BATCH_SIZE {
+                msg!("Error: Batch of {} entries exceeds the maximum of {}", entries.len(), MAX_BATCH_SIZE);
+                return Err(AccountDemoError::BatchTooLarge.into());
+            }
+        }
+
+        // Fail up front with a clear error, before any handler runs out of accounts
+        if accounts.is_empty() {
+            msg!("Error: No accounts provided, {} expects at least one", variant);
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let required = instruction.required_account_count();
+        if accounts.len() < required {
+            msg!("Error: {} expects at least {} accounts, got {}", variant, required, accounts.len());
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        if instruction.is_mutating() {
+            Self::check_not_paused(program_id, accounts)?;
+        }
+
+        log_compute_units(variant, "start");
+        let result = match instruction {
             AccountDemoInstruction::Initialize { name, message } => {
                 msg!("Instruction: Initialize {{ name: {}, message: {} }}", name, message);
-                Self::process_initialize(program_id, accounts, name, message)
+                Self::process_initialize(program_id, accounts, name, message, 0)
             }
             AccountDemoInstruction::UpdateMessage { message } => {
                 msg!("Instruction: UpdateMessage {{ message: {} }}", message);
-                Self::process_update_message(accounts, message)
+                Self::process_update_message(program_id, accounts, message, false, false, false)
+            }
+            AccountDemoInstruction::UpdateMessageIfChanged { message } => {
+                msg!("Instruction: UpdateMessageIfChanged {{ message: {} }}", message);
+                Self::process_update_message(program_id, accounts, message, true, false, false)
+            }
+            AccountDemoInstruction::UpdateMessageCompact { message } => {
+                msg!("Instruction: UpdateMessageCompact {{ message: {} }}", message);
+                Self::process_update_message(program_id, accounts, message, false, false, true)
+            }
+            AccountDemoInstruction::GetPda => {
+                msg!("Instruction: GetPda");
+                Self::process_get_pda(program_id, accounts)
+            }
+            AccountDemoInstruction::SignedUpdateMessage { message, signature, signer_pubkey } => {
+                msg!(
+                    "Instruction: SignedUpdateMessage {{ message: {}, signer_pubkey: {} }}",
+                    message, Pubkey::new_from_array(signer_pubkey)
+                );
+                Self::process_signed_update_message(program_id, accounts, message, &signature, &signer_pubkey)
+            }
+            AccountDemoInstruction::InitializeRaw { packed } => {
+                msg!("Instruction: InitializeRaw {{ packed: {} bytes }}", packed.len());
+                Self::process_initialize_raw(program_id, accounts, &packed)
+            }
+            AccountDemoInstruction::VerifyIntegrity => {
+                msg!("Instruction: VerifyIntegrity");
+                Self::process_verify_integrity(program_id, accounts)
+            }
+            AccountDemoInstruction::SetLabel { label } => {
+                msg!("Instruction: SetLabel {{ label: {} }}", label);
+                Self::process_set_label(program_id, accounts, label)
+            }
+            AccountDemoInstruction::ClearLabel => {
+                msg!("Instruction: ClearLabel");
+                Self::process_set_label(program_id, accounts, String::new())
+            }
+            AccountDemoInstruction::CanonicalizePda => {
+                msg!("Instruction: CanonicalizePda");
+                Self::process_canonicalize_pda(program_id, accounts)
+            }
+            AccountDemoInstruction::SetAttester { attester } => {
+                msg!("Instruction: SetAttester {{ attester: {} }}", Pubkey::new_from_array(attester));
+                Self::process_set_attester(program_id, accounts, attester)
+            }
+            AccountDemoInstruction::InitConfig { admin } => {
+                msg!("Instruction: InitConfig {{ admin: {} }}", Pubkey::new_from_array(admin));
+                Self::process_init_config(program_id, accounts, admin)
+            }
+            AccountDemoInstruction::SetAvatarHash { hash } => {
+                msg!("Instruction: SetAvatarHash {{ set: {} }}", hash.is_some());
+                Self::process_set_avatar_hash(program_id, accounts, hash)
+            }
+            AccountDemoInstruction::CopyMessage => {
+                msg!("Instruction: CopyMessage");
+                Self::process_copy_message(program_id, accounts)
+            }
+            AccountDemoInstruction::WithdrawLamports { amount } => {
+                msg!("Instruction: WithdrawLamports {{ amount: {} }}", amount);
+                Self::process_withdraw_lamports(program_id, accounts, amount)
+            }
+            AccountDemoInstruction::SetMessageAt { index, message } => {
+                msg!("Instruction: SetMessageAt {{ index: {}, message: {} }}", index, message);
+                Self::process_set_message_at(program_id, accounts, index, message)
+            }
+            AccountDemoInstruction::GetMessageAt { index } => {
+                msg!("Instruction: GetMessageAt {{ index: {} }}", index);
+                Self::process_get_message_at(program_id, accounts, index)
+            }
+            AccountDemoInstruction::SetAlias { alias } => {
+                msg!("Instruction: SetAlias {{ alias: {} }}", alias);
+                Self::process_set_alias(program_id, accounts, alias)
+            }
+            AccountDemoInstruction::CloseAccount => {
+                msg!("Instruction: CloseAccount");
+                Self::process_close_account(program_id, accounts)
             }
+            AccountDemoInstruction::MergeInto => {
+                msg!("Instruction: MergeInto");
+                Self::process_merge_into(program_id, accounts)
+            }
+            AccountDemoInstruction::GetSummary => {
+                msg!("Instruction: GetSummary");
+                Self::process_get_summary(program_id, accounts)
+            }
+            AccountDemoInstruction::VerifyTag { expected } => {
+                msg!("Instruction: VerifyTag");
+                Self::process_verify_tag(program_id, accounts, &expected)
+            }
+            AccountDemoInstruction::SwapMessage { new } => {
+                msg!("Instruction: SwapMessage {{ new: {} }}", new);
+                Self::process_swap_message(program_id, accounts, new)
+            }
+            AccountDemoInstruction::TopUpRent => {
+                msg!("Instruction: TopUpRent");
+                Self::process_top_up_rent(program_id, accounts)
+            }
+            AccountDemoInstruction::InitializeMany { entries } => {
+                msg!("Instruction: InitializeMany {{ entries: {} }}", entries.len());
+                Self::process_initialize_many(program_id, accounts, entries)
+            }
+            AccountDemoInstruction::SetPaused { paused } => {
+                msg!("Instruction: SetPaused {{ paused: {} }}", paused);
+                Self::process_set_paused(program_id, accounts, paused)
+            }
+            AccountDemoInstruction::CheckOwner => {
+                msg!("Instruction: CheckOwner");
+                Self::process_check_owner(program_id, accounts)
+            }
+            AccountDemoInstruction::SetFlags { mask, value } => {
+                msg!("Instruction: SetFlags {{ mask: {:#04x}, value: {:#04x} }}", mask, value);
+                Self::process_set_flags(program_id, accounts, mask, value)
+            }
+            AccountDemoInstruction::InitializeWithReserve { name, message, reserve_bytes } => {
+                msg!(
+                    "Instruction: InitializeWithReserve {{ name: {}, message: {}, reserve_bytes: {} }}",
+                    name, message, reserve_bytes
+                );
+                Self::process_initialize(program_id, accounts, name, message, reserve_bytes)
+            }
+            #[cfg(feature = "debug-tools")]
+            AccountDemoInstruction::DumpRaw => {
+                msg!("Instruction: DumpRaw");
+                Self::process_dump_raw(accounts)
+            }
+            // Unreachable: variant_name rejects the tag without the feature
+            #[cfg(not(feature = "debug-tools"))]
+            AccountDemoInstruction::DumpRaw => Err(AccountDemoError::InvalidInstructionData.into()),
+        };
+        log_compute_units(variant, "end");
+        
+        result
+    }
+
+    fn check_not_paused(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Mutating instructions carry the Config PDA as their last account
+        let config_account = accounts.last().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        check_config_account(program_id, config_account)?;
+        
+        if Config::is_paused(&config_account.data.borrow())? {
+            msg!("Error: Program is paused");
+            return Err(AccountDemoError::ProgramPaused.into());
         }
+        
+        Ok(())
     }
 
     fn process_initialize(
@@ -48,63 +305,153 @@ impl Processor {
         accounts: &[AccountInfo],
         name: String,
         message: String,
+        reserve_bytes: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        Self::initialize_account(program_id, user_account, user_data_account, system_program, name, message, reserve_bytes)
+    }
+
+    fn process_initialize_raw(program_id: &Pubkey, accounts: &[AccountInfo], packed: &[u8]) -> ProgramResult {
+        let (name, message) = unpack_name_and_message(packed).map_err(|err| {
+            msg!("Error: Malformed packed name and message");
+            err
+        })?;
+        
+        Self::process_initialize(program_id, accounts, name, message, 0)
+    }
+
+    fn process_initialize_many(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        entries: Vec<(String, String)>,
     ) -> ProgramResult {
         let accounts_iter = &mut accounts.iter();
         
         // Get accounts
-        let user_account = next_account_info(accounts_iter)?;
-        let user_data_account = next_account_info(accounts_iter)?;
-        let rent_account = next_account_info(accounts_iter)?;
-        let system_program = next_account_info(accounts_iter)?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Each entry consumes an (owner, user data account) pair
+        for (index, (name, message)) in entries.into_iter().enumerate() {
+            let user_account = expect_account(accounts_iter, "owner")?;
+            let user_data_account = expect_account(accounts_iter, "user data")?;
+            
+            msg!("Initializing batch entry {} for user: {}", index, user_account.key);
+            Self::initialize_account(program_id, user_account, user_data_account, system_program, name, message, 0)?;
+        }
         
+        Ok(())
+    }
+
+    fn initialize_account<'a>(
+        program_id: &Pubkey,
+        user_account: &AccountInfo<'a>,
+        user_data_account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        name: String,
+        message: String,
+        reserve_bytes: u32,
+    ) -> ProgramResult {
         // Verify account ownership
         if user_data_account.owner != program_id && !user_data_account.data_is_empty() {
             return Err(ProgramError::IncorrectProgramId);
         }
         
+        // Executable accounts can never hold UserData
+        check_not_executable(user_data_account, "user data")?;
+        
         // Check if user is signer
         if !user_account.is_signer {
             return Err(ProgramError::MissingRequiredSignature);
         }
         
-        // Get rent sysvar
-        let rent = Rent::from_account_info(rent_account)?;
+        // The signer and the data account must be different accounts
+        if user_account.key == user_data_account.key {
+            msg!("Error: Signer and data account are the same account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        // Calculate account size, including any reserve, and rent
+        let data_size = UserData::reserved_size(&name, &message, reserve_bytes)?;
+        let (_, lamports_required) = rent_and_minimum(data_size)?;
+        
+        check_message_not_blank(&message)?;
+        let now = Clock::get()?.unix_timestamp;
         
-        // Calculate account size and rent
-        let data_size = UserData::get_size(&name, &message);
-        let lamports_required = rent.minimum_balance(data_size);
+        // The owner stored in the account is also the PDA seed, and must be
+        // the signer. Derive the address from the stored owner so a refactor
+        // can't let the seed, the stored owner and the signer drift apart.
+        let mut account_data = UserData::new(*user_account.key, name, message)?;
+        let owner = account_data.get_owner();
+        if !pubkeys_eq_ct(&owner, user_account.key) {
+            msg!("Error: Stored owner does not match the signer");
+            return Err(AccountDemoError::NotOwner.into());
+        }
+        
+        // Verify the account is the owner's PDA, whether or not it exists yet
+        let (expected_address, bump) = pda::find_user_data_address(&owner, program_id);
+        if expected_address != *user_data_account.key {
+            msg!("Error: Account is not a PDA");
+            return Err(ProgramError::InvalidArgument);
+        }
+        account_data.set_canonical(bump);
+        account_data.name_updated_at = now;
+        account_data.message_updated_at = now;
         
         // Create account if it doesn't exist
         if user_data_account.data_is_empty() {
             msg!("Creating user data account...");
             
-            // Verify the account is a PDA
-            let (expected_address, bump) = Pubkey::find_program_address(
-                &[b"user-data", user_account.key.as_ref()],
-                program_id,
-            );
-            
-            if expected_address != *user_data_account.key {
-                msg!("Error: Account is not a PDA");
-                return Err(ProgramError::InvalidArgument);
-            }
-            
             msg!("Creating account with {} bytes", data_size);
-            invoke_signed(
-                &system_instruction::create_account(
-                    user_account.key,
-                    user_data_account.key,
-                    lamports_required,
-                    data_size as u64,
-                    program_id,
-                ),
-                &[
-                    user_account.clone(),
-                    user_data_account.clone(),
-                    system_program.clone(),
-                ],
-                &[&[b"user-data", user_account.key.as_ref(), &[bump]]],
-            )?;
+            let bump_seed = [bump];
+            let signer_seeds = pda::signer_seeds(&owner, &bump_seed);
+            if user_data_account.lamports() == 0 {
+                invoke_signed(
+                    &system_instruction::create_account(
+                        user_account.key,
+                        user_data_account.key,
+                        lamports_required,
+                        data_size as u64,
+                        program_id,
+                    ),
+                    &[
+                        user_account.clone(),
+                        user_data_account.clone(),
+                        system_program.clone(),
+                    ],
+                    &[&signer_seeds],
+                )?;
+            } else {
+                // create_account fails on an address that already holds
+                // lamports, so fund, allocate and assign it step by step
+                msg!("Account is pre-funded with {} lamports", user_data_account.lamports());
+                let shortfall = lamports_required.saturating_sub(user_data_account.lamports());
+                if shortfall > 0 {
+                    invoke(
+                        &system_instruction::transfer(user_account.key, user_data_account.key, shortfall),
+                        &[
+                            user_account.clone(),
+                            user_data_account.clone(),
+                            system_program.clone(),
+                        ],
+                    )?;
+                }
+                invoke_signed(
+                    &system_instruction::allocate(user_data_account.key, data_size as u64),
+                    &[user_data_account.clone(), system_program.clone()],
+                    &[&signer_seeds],
+                )?;
+                invoke_signed(
+                    &system_instruction::assign(user_data_account.key, program_id),
+                    &[user_data_account.clone(), system_program.clone()],
+                    &[&signer_seeds],
+                )?;
+            }
             
             msg!("Account created successfully");
         } else {
@@ -112,9 +459,8 @@ impl Processor {
         }
         
         // Initialize account data
-        let account_data = UserData::new(*user_account.key, name, message)?;
         let mut data = user_data_account.data.borrow_mut();
-        account_data.serialize(&mut &mut data[..])?;
+        account_data.save(&mut data)?;
         
         msg!("Account data initialized successfully");
         
@@ -122,84 +468,1349 @@ impl Processor {
     }
 
     fn process_update_message(
+        program_id: &Pubkey,
         accounts: &[AccountInfo],
         message: String,
+        require_change: bool,
+        verified: bool,
+        compact: bool,
     ) -> ProgramResult {
         let accounts_iter = &mut accounts.iter();
         
         // Get accounts
-        let user_account = next_account_info(accounts_iter)?;
-        let user_data_account = next_account_info(accounts_iter)?;
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = if compact {
+            Some(expect_account(accounts_iter, "system program")?)
+        } else {
+            None
+        };
         
         msg!("Processing update message for user: {}", user_account.key);
         msg!("User data account: {}", user_data_account.key);
         
-        // Check if user is signer
-        if !user_account.is_signer {
-            msg!("Error: User is not a signer");
-            return Err(ProgramError::MissingRequiredSignature);
-        }
-        
         // Log account data
-        let account_data = user_data_account.data.borrow();
-        msg!("Account data length: {}", account_data.len());
-        msg!("Account data: {:?}", account_data);
-        
-        // Deserialize account data
-        msg!("Attempting to deserialize account data...");
-        let mut user_data = match UserData::safe_deserialize(&account_data) {
-            Ok(data) => {
-                msg!("Successfully deserialized account data");
-                msg!("Deserialized data: {:?}", data);
-                data
-            }
-            Err(err) => {
-                msg!("Failed to deserialize account data: {:?}", err);
-                return Err(AccountDemoError::DataTypeMismatch.into());
-            }
-        };
-        
-        // Check if account is initialized
-        msg!("Checking if account is initialized...");
-        if !user_data.is_initialized {
-            msg!("Error: Account is not initialized");
-            return Err(AccountDemoError::NotInitialized.into());
-        }
+        msg!("Account data length: {}", user_data_account.data_len());
+        msg!("Account data: {:?}", user_data_account.data.borrow());
         
-        // Check if user is the owner of the account
-        msg!("Checking account ownership...");
-        let owner = user_data.get_owner();
-        msg!("Account owner: {}", owner);
-        msg!("User account: {}", user_account.key);
-        if owner != *user_account.key {
-            msg!("Error: User is not the account owner");
-            return Err(AccountDemoError::NotOwner.into());
-        }
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        msg!("Deserialized data: {:?}", user_data);
         
         // Check message length
         if message.len() > UserData::MAX_MESSAGE_LENGTH {
             msg!("Error: Message too long");
             return Err(AccountDemoError::MessageTooLong.into());
         }
+        check_message_not_blank(&message)?;
         
-        // Calculate required account size
-        let required_size = UserData::get_size(&user_data.name, &message);
-        if required_size > account_data.len() {
-            msg!("Error: Account size too small. Required: {}, Available: {}", required_size, account_data.len());
-            return Err(ProgramError::AccountDataTooSmall);
+        // Optionally refuse to pay for a write that changes nothing
+        if require_change && constant_time_eq(user_data.message.as_bytes(), message.as_bytes()) {
+            msg!("Error: New message is the same as the current one");
+            return Err(AccountDemoError::NoChange.into());
+        }
+        
+        // Enforce a minimum slot gap between updates
+        let clock = Clock::get()?;
+        let slots_elapsed = clock.slot.saturating_sub(user_data.last_update_slot);
+        if slots_elapsed < UserData::MIN_SLOT_GAP {
+            msg!("Error: Only {} slots since the last update, minimum is {}", slots_elapsed, UserData::MIN_SLOT_GAP);
+            return Err(AccountDemoError::TooFrequent.into());
         }
         
         // Update message and counter
         msg!("Updating message and counter...");
         user_data.message = message;
-        user_data.update_count += 1;
+        user_data.message_updated_at = clock.unix_timestamp;
+        user_data.increment_update_count()?;
+        user_data.last_update_slot = clock.slot;
+        if verified {
+            user_data.flags |= UserData::FLAG_VERIFIED;
+        } else {
+            user_data.flags &= !UserData::FLAG_VERIFIED;
+        }
+        
+        // Calculate required account size
+        let required_size = user_data.size_of_current();
+        if required_size > user_data_account.data_len() {
+            msg!("Error: Account size too small. Required: {}, Available: {}", required_size, user_data_account.data_len());
+            return Err(ProgramError::AccountDataTooSmall);
+        }
         
         // Save updated data back to account
         msg!("Saving updated data back to account...");
-        let mut data = user_data_account.data.borrow_mut();
-        user_data.serialize(&mut &mut data[..])?;
+        user_data.save(&mut user_data_account.data.borrow_mut())?;
+        
+        // Optionally shrink to the exact size, refunding the freed rent
+        if let Some(system_program) = system_program {
+            if required_size < user_data_account.data_len() {
+                Self::resize_account(user_account, user_data_account, system_program, required_size)?;
+            }
+        }
         
         msg!("User data message updated successfully");
+        msg!(
+            "event:update owner={} update_count={} message_len={}",
+            user_account.key,
+            user_data.update_count,
+            user_data.message.len()
+        );
+        Ok(())
+    }
+
+    fn process_signed_update_message(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        message: String,
+        signature: &[u8; attest::SIGNATURE_BYTES],
+        signer_pubkey: &[u8; attest::PUBKEY_BYTES],
+    ) -> ProgramResult {
+        // The owner and data account are read again by the update itself
+        let owner_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let instructions_sysvar = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let config_account = accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        
+        // Only the owner, who the update checks signed and owns the account,
+        // or the configured attester may vouch for the message
+        check_config_account(program_id, config_account)?;
+        let signer = Pubkey::new_from_array(*signer_pubkey);
+        let is_attester = Config::attester(&config_account.data.borrow())?
+            .map_or(false, |attester| pubkeys_eq_ct(&attester, &signer));
+        if !pubkeys_eq_ct(owner_account.key, &signer) && !is_attester {
+            msg!("Error: Message is not signed by the owner or the attester");
+            return Err(AccountDemoError::InvalidAttestation.into());
+        }
+        
+        if *instructions_sysvar.key != instructions::id() {
+            msg!("Error: Account is not the instructions sysvar");
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        // The ed25519 program must have verified the signature just before us
+        let current_index = instructions::load_current_index_checked(instructions_sysvar)?;
+        if current_index == 0 {
+            msg!("Error: No ed25519 instruction before this one");
+            return Err(AccountDemoError::InvalidAttestation.into());
+        }
+        let verify_instruction =
+            instructions::load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+        if verify_instruction.program_id != ed25519_program::id()
+            || !attest::verify_ed25519_instruction(
+                &verify_instruction.data,
+                signer_pubkey,
+                signature,
+                message.as_bytes(),
+            )
+        {
+            msg!("Error: Preceding instruction does not verify this message's signature");
+            return Err(AccountDemoError::InvalidAttestation.into());
+        }
+        
+        Self::process_update_message(program_id, accounts, message, false, true, false)
+    }
+
+    fn process_set_message_at(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        index: u8,
+        message: String,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        
+        // Store the message in its slot
+        user_data.set_message_at(index, message)?;
+        user_data.increment_update_count()?;
+        
+        // Grow the account if the slots no longer fit
+        let required_size = user_data.size_of_current();
+        if required_size > user_data_account.data_len() {
+            Self::resize_account(user_account, user_data_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to account
+        let mut data = user_data_account.data.borrow_mut();
+        user_data.save(&mut data)?;
+        
+        msg!("Message slot {} updated successfully", index);
+        Ok(())
+    }
+
+    fn process_get_message_at(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        index: u8,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let expected_owner = accounts_iter.next();
+        
+        if index as usize >= UserData::MAX_SLOTS {
+            msg!("Error: Slot index {} out of range", index);
+            return Err(AccountDemoError::SlotOutOfRange.into());
+        }
+        
+        let user_data = load_user_data(program_id, user_data_account, expected_owner.map(|owner| owner.key))?;
+        
+        // Slots that were never written read back as empty
+        let message = user_data
+            .messages
+            .get(index as usize)
+            .map(String::as_str)
+            .unwrap_or("");
+        msg!("Message at slot {}: {}", index, message);
+        
+        Ok(())
+    }
+
+    fn process_set_alias(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        alias: String,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        
+        // Update the alias, an empty alias clears it
+        user_data.set_alias(alias)?;
+        
+        // Grow the account if the alias no longer fits
+        let required_size = user_data.size_of_current();
+        if required_size > user_data_account.data_len() {
+            Self::resize_account(user_account, user_data_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to account
+        let mut data = user_data_account.data.borrow_mut();
+        user_data.save(&mut data)?;
+        
+        msg!("Alias updated successfully");
+        Ok(())
+    }
+
+    fn process_set_label(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        label: String,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        
+        // Update the label, an empty label clears it
+        user_data.set_label(label)?;
+        
+        // Grow the account if the label no longer fits
+        let required_size = user_data.size_of_current();
+        if required_size > user_data_account.data_len() {
+            Self::resize_account(user_account, user_data_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to account
+        let mut data = user_data_account.data.borrow_mut();
+        user_data.save(&mut data)?;
+        
+        msg!("Label updated successfully");
+        Ok(())
+    }
+
+    fn process_canonicalize_pda(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        
+        if user_data.is_canonical() {
+            msg!("Account is already canonical");
+            return Ok(());
+        }
+        
+        // Re-derive from the stored owner; only the canonical bump gives this address
+        let (expected_address, bump) = pda::find_user_data_address(&user_data.get_owner(), program_id);
+        if expected_address != *user_data_account.key {
+            msg!("Error: Account is not the owner's canonical PDA");
+            return Err(AccountDemoError::InvalidPda.into());
+        }
+        user_data.set_canonical(bump);
+        
+        // Grow the account if the stored bump no longer fits
+        let required_size = user_data.size_of_current();
+        if required_size > user_data_account.data_len() {
+            Self::resize_account(user_account, user_data_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to account
+        let mut data = user_data_account.data.borrow_mut();
+        user_data.save(&mut data)?;
+        
+        msg!("Account marked canonical with bump {}", bump);
+        Ok(())
+    }
+
+    fn process_set_avatar_hash(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        hash: Option<[u8; 32]>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        
+        user_data.avatar_hash = hash;
+        
+        // Grow the account if the hash no longer fits
+        let required_size = user_data.size_of_current();
+        if required_size > user_data_account.data_len() {
+            Self::resize_account(user_account, user_data_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to account
+        let mut data = user_data_account.data.borrow_mut();
+        user_data.save(&mut data)?;
+        
+        msg!("Avatar hash {}", if hash.is_some() { "set" } else { "cleared" });
+        Ok(())
+    }
+
+    fn process_set_flags(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        mask: u8,
+        value: u8,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        
+        // Apply every selected bit at once
+        if let Err(err) = user_data.set_flags(mask, value) {
+            msg!("Error: Mask {:#04x} touches flags the owner can't change", mask);
+            return Err(err);
+        }
+        
+        // Flags are fixed size, so the account never needs to grow
+        let mut data = user_data_account.data.borrow_mut();
+        user_data.save(&mut data)?;
+        
+        msg!("Flags set to {:#04x}", user_data.flags);
         Ok(())
     }
+
+    fn process_close_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        
+        // Check the signer owns the account before touching it
+        let user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        check_canonical_pda(program_id, user_data_account, &user_data)?;
+        
+        Self::close_account(user_data_account, user_account)?;
+        
+        msg!("Account closed successfully");
+        Ok(())
+    }
+
+    fn process_merge_into(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let source_account = expect_account(accounts_iter, "source")?;
+        let destination_account = expect_account(accounts_iter, "destination")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Merging an account into itself would close it
+        if source_account.key == destination_account.key {
+            msg!("Error: Source and destination are the same account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        // Check the signer owns both accounts before touching them
+        let source = load_owned_user_data(program_id, user_account, source_account)?;
+        let mut destination = load_owned_user_data(program_id, user_account, destination_account)?;
+        
+        // The source is closed, so it must be the owner's canonical PDA
+        check_canonical_pda(program_id, source_account, &source)?;
+        
+        // Append the source's message and slots after the destination's slots
+        let merged = std::iter::once(source.message)
+            .chain(source.messages)
+            .filter(|message| !message.is_empty());
+        for message in merged {
+            let index = destination.messages.len();
+            if index >= UserData::MAX_SLOTS {
+                msg!("Error: Not enough free slots in the destination");
+                return Err(AccountDemoError::SlotOutOfRange.into());
+            }
+            destination.set_message_at(index as u8, message)?;
+        }
+        destination.increment_update_count()?;
+        
+        // Grow the destination if the merged slots no longer fit
+        let required_size = destination.size_of_current();
+        if required_size > destination_account.data_len() {
+            Self::resize_account(user_account, destination_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to the destination
+        let mut data = destination_account.data.borrow_mut();
+        destination.save(&mut data)?;
+        drop(data);
+        
+        // Close the source and refund its rent to the owner
+        Self::close_account(source_account, user_account)?;
+        
+        msg!("Accounts merged successfully");
+        Ok(())
+    }
+
+    fn process_copy_message(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let source_account = expect_account(accounts_iter, "source")?;
+        let destination_account = expect_account(accounts_iter, "destination")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Copying an account onto itself would only bump its counter
+        if source_account.key == destination_account.key {
+            msg!("Error: Source and destination are the same account");
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        // Check the signer owns both accounts before touching them
+        let source = load_owned_user_data(program_id, user_account, source_account)?;
+        let mut destination = load_owned_user_data(program_id, user_account, destination_account)?;
+        
+        destination.message = source.message;
+        destination.message_updated_at = Clock::get()?.unix_timestamp;
+        destination.increment_update_count()?;
+        
+        // Grow the destination if the copied message no longer fits
+        let required_size = destination.size_of_current();
+        if required_size > destination_account.data_len() {
+            Self::resize_account(user_account, destination_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to the destination
+        let mut data = destination_account.data.borrow_mut();
+        destination.save(&mut data)?;
+        
+        msg!("Message copied successfully");
+        Ok(())
+    }
+
+    fn process_get_summary(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let expected_owner = accounts_iter.next();
+        
+        let user_data = load_user_data(program_id, user_data_account, expected_owner.map(|owner| owner.key))?;
+        
+        let summary = user_data.summary();
+        msg!("Summary: {:?}", summary);
+        set_return_data(&summary.try_to_vec()?);
+        
+        Ok(())
+    }
+
+    fn process_verify_integrity(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        
+        // Verify account ownership
+        if user_data_account.owner != program_id {
+            msg!("Error: Account is not owned by this program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        
+        // Loading decodes the data and recomputes its checksum
+        match UserData::load_initialized(&user_data_account.data.borrow()) {
+            Ok(_) => {
+                msg!("Integrity: OK");
+                Ok(())
+            }
+            Err(err) => {
+                if err == AccountDemoError::ChecksumMismatch.into()
+                    || err == AccountDemoError::DataTypeMismatch.into()
+                {
+                    msg!("Integrity: CORRUPT");
+                }
+                Err(err)
+            }
+        }
+    }
+
+    fn process_check_owner(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let key_account = expect_account(accounts_iter, "key")?;
+        
+        // Verify account ownership
+        if user_data_account.owner != program_id {
+            msg!("Error: Account is not owned by this program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        
+        let user_data = UserData::load_initialized(&user_data_account.data.borrow())?;
+        
+        let is_owner = user_data.is_owner(key_account.key);
+        msg!("Is owner: {}", is_owner);
+        set_return_data(&[is_owner as u8]);
+        
+        Ok(())
+    }
+
+    #[cfg(feature = "debug-tools")]
+    fn process_dump_raw(accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let account = expect_account(accounts_iter, "account")?;
+        
+        let data = account.data.borrow();
+        msg!("Raw data of {} ({} bytes):", account.key, data.len());
+        for line in hex_lines(&data) {
+            msg!("{}", line);
+        }
+        
+        Ok(())
+    }
+
+    fn process_get_pda(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let owner_account = expect_account(accounts_iter, "owner")?;
+        
+        let bytes = pda::user_data_address_bytes(owner_account.key, program_id);
+        msg!("PDA bump: {}", bytes[32]);
+        set_return_data(&bytes);
+        
+        Ok(())
+    }
+
+    fn process_verify_tag(program_id: &Pubkey, accounts: &[AccountInfo], expected: &[u8]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let expected_owner = accounts_iter.next();
+        
+        let user_data = load_user_data(program_id, user_data_account, expected_owner.map(|owner| owner.key))?;
+        
+        if !tag::verify_tag(&user_data.message, expected) {
+            msg!("Error: Tag mismatch");
+            return Err(AccountDemoError::TagMismatch.into());
+        }
+        
+        msg!("Tag verified successfully");
+        Ok(())
+    }
+
+    fn process_swap_message(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new: String,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Check the signer owns the account before touching it
+        let mut user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        
+        // Check message length
+        if new.len() > UserData::MAX_MESSAGE_LENGTH {
+            msg!("Error: Message too long");
+            return Err(AccountDemoError::MessageTooLong.into());
+        }
+        check_message_not_blank(&new)?;
+        
+        // Swap in the new message and bump the counter
+        let old = std::mem::replace(&mut user_data.message, new);
+        user_data.message_updated_at = Clock::get()?.unix_timestamp;
+        user_data.increment_update_count()?;
+        
+        // Grow the account if the new message no longer fits
+        let required_size = user_data.size_of_current();
+        if required_size > user_data_account.data_len() {
+            Self::resize_account(user_account, user_data_account, system_program, required_size)?;
+        }
+        
+        // Save updated data back to account
+        let mut data = user_data_account.data.borrow_mut();
+        user_data.save(&mut data)?;
+        
+        // Hand the previous message back to the caller
+        set_return_data(old.as_bytes());
+        
+        msg!("Message swapped successfully");
+        Ok(())
+    }
+
+    fn process_top_up_rent(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let payer_account = expect_account(accounts_iter, "payer")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Check if payer is signer
+        if !payer_account.is_signer {
+            msg!("Error: Payer is not a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        
+        // Verify account ownership
+        if user_data_account.owner != program_id {
+            msg!("Error: Account is not owned by this program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        
+        // Executable accounts can never hold UserData
+        check_not_executable(user_data_account, "user data")?;
+        
+        // Work out the shortfall for the account's current size
+        let (_, lamports_required) = rent_and_minimum(user_data_account.data_len())?;
+        let lamports = user_data_account.lamports();
+        if lamports >= lamports_required {
+            msg!("Account already holds {} of {} required lamports", lamports, lamports_required);
+            return Err(AccountDemoError::AlreadyRentExempt.into());
+        }
+        
+        let shortfall = lamports_required - lamports;
+        msg!("Transferring {} lamports to cover rent", shortfall);
+        invoke(
+            &system_instruction::transfer(payer_account.key, user_data_account.key, shortfall),
+            &[payer_account.clone(), user_data_account.clone(), system_program.clone()],
+        )?;
+        
+        msg!("Account topped up successfully");
+        Ok(())
+    }
+
+    fn process_init_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        admin: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let authority_account = expect_account(accounts_iter, "upgrade authority")?;
+        let config_account = expect_account(accounts_iter, "config")?;
+        let program_data_account = expect_account(accounts_iter, "program data")?;
+        let system_program = expect_account(accounts_iter, "system program")?;
+        
+        // Only whoever can upgrade the program may choose its admin
+        if !authority_account.is_signer {
+            msg!("Error: Upgrade authority is not a signer");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        check_upgrade_authority(program_id, program_data_account, authority_account.key)?;
+        
+        let (config_address, bump) = pda::find_config_address(program_id);
+        if *config_account.key != config_address {
+            msg!("Error: Account is not the program config");
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        if config_account.data_is_empty() {
+            msg!("Creating program config...");
+            let (_, lamports_required) = rent_and_minimum(Config::LEN)?;
+            let bump_seed = [bump];
+            invoke_signed(
+                &system_instruction::create_account(
+                    authority_account.key,
+                    config_account.key,
+                    lamports_required,
+                    Config::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    authority_account.clone(),
+                    config_account.clone(),
+                    system_program.clone(),
+                ],
+                &[&pda::config_signer_seeds(&bump_seed)],
+            )?;
+        } else if config_account.owner != program_id {
+            msg!("Error: Config is not owned by this program");
+            return Err(ProgramError::IncorrectProgramId);
+        } else if Config::load_initialized(&config_account.data.borrow()).is_ok() {
+            msg!("Error: Program config already exists");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        
+        let config = Config {
+            is_initialized: true,
+            admin,
+            paused: false,
+            attester: [0; 32],
+        };
+        write_account_data(&config, &mut config_account.data.borrow_mut())?;
+        
+        msg!("Program config created");
+        Ok(())
+    }
+
+    fn process_set_paused(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        paused: bool,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let admin_account = expect_account(accounts_iter, "admin")?;
+        let config_account = expect_account(accounts_iter, "config")?;
+        
+        // Only the admin chosen by InitConfig may pause or resume the program
+        let mut config = load_admin_config(program_id, admin_account, config_account)?;
+        config.paused = paused;
+        write_account_data(&config, &mut config_account.data.borrow_mut())?;
+        
+        msg!("Program {}", if paused { "paused" } else { "resumed" });
+        Ok(())
+    }
+
+    fn process_set_attester(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        attester: [u8; 32],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let admin_account = expect_account(accounts_iter, "admin")?;
+        let config_account = expect_account(accounts_iter, "config")?;
+        
+        let mut config = load_admin_config(program_id, admin_account, config_account)?;
+        config.attester = attester;
+        write_account_data(&config, &mut config_account.data.borrow_mut())?;
+        
+        msg!("Attester {}", if attester == [0; 32] { "removed" } else { "set" });
+        Ok(())
+    }
+
+    fn process_withdraw_lamports(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        
+        // Get accounts
+        let user_account = expect_account(accounts_iter, "owner")?;
+        let user_data_account = expect_account(accounts_iter, "user data")?;
+        
+        // Check the signer owns the account before touching it
+        let user_data = load_owned_user_data(program_id, user_account, user_data_account)?;
+        check_canonical_pda(program_id, user_data_account, &user_data)?;
+        
+        // Only lamports above the rent-exempt minimum can leave
+        let (_, minimum) = rent_and_minimum(user_data_account.data_len())?;
+        let remaining = check_withdrawal(user_data_account.lamports(), minimum, amount)?;
+        let owner_lamports = user_account
+            .lamports()
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        **user_data_account.lamports.borrow_mut() = remaining;
+        **user_account.lamports.borrow_mut() = owner_lamports;
+        
+        msg!("Withdrew {} lamports, {} remain", amount, remaining);
+        Ok(())
+    }
+
+    fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+        // Zero the data first so the stale UserData (including its flags)
+        // can't be read back if an account is re-created at the same address
+        let mut data = account.data.borrow_mut();
+        let data_len = data.len();
+        sol_memset(&mut data, 0, data_len);
+        drop(data);
+        
+        // Move all lamports to the destination
+        let lamports = account.lamports();
+        let destination_lamports = destination
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::InvalidArgument)?;
+        **destination.lamports.borrow_mut() = destination_lamports;
+        **account.lamports.borrow_mut() = 0;
+        
+        msg!("Refunded {} lamports", lamports);
+        Ok(())
+    }
+
+    /// Resize `account` to `new_size` bytes, keeping it rent exempt: the
+    /// payer covers any shortfall when growing and gets freed rent back
+    /// when shrinking
+    ///
+    /// Bytes exposed by growing always read as zero, even when an earlier
+    /// shrink in the same instruction left old data behind them.
+    ///
+    /// ```
+    /// use std::convert::TryInto;
+    /// use solana_clean_demo::processor::Processor;
+    /// use solana_program::{entrypoint::{deserialize, MAX_PERMITTED_DATA_INCREASE}, program_stubs, pubkey::Pubkey, rent::Rent};
+    ///
+    /// struct Sysvars;
+    /// impl program_stubs::SyscallStubs for Sysvars {
+    ///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    ///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+    ///         0
+    ///     }
+    /// }
+    /// program_stubs::set_syscall_stubs(Box::new(Sysvars));
+    ///
+    /// // Lay the accounts out the way the runtime serializes them, so the
+    /// // account data can be resized in place
+    /// fn serialize(accounts: &[(Pubkey, bool, bool, u64, Vec<u8>, Pubkey)]) -> Vec<u64> {
+    ///     let mut input = (accounts.len() as u64).to_le_bytes().to_vec();
+    ///     for (key, is_signer, is_writable, lamports, data, owner) in accounts {
+    ///         input.extend_from_slice(&[0xff, *is_signer as u8, *is_writable as u8, 0, 0, 0, 0, 0]);
+    ///         input.extend_from_slice(key.as_ref());
+    ///         input.extend_from_slice(owner.as_ref());
+    ///         input.extend_from_slice(&lamports.to_le_bytes());
+    ///         input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    ///         input.extend_from_slice(data);
+    ///         input.resize((input.len() + MAX_PERMITTED_DATA_INCREASE + 7) / 8 * 8, 0);
+    ///         input.extend_from_slice(&0u64.to_le_bytes()); // rent epoch
+    ///     }
+    ///     input.extend_from_slice(&[0u8; 8 + 32]); // no instruction data, program id
+    ///     input.chunks(8).map(|word| u64::from_ne_bytes(word.try_into().unwrap())).collect()
+    /// }
+    ///
+    /// let (program_id, payer_key, account_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    /// let system_key = solana_program::system_program::id();
+    ///
+    /// // Enough lamports that growing back needs no transfer from the payer
+    /// let lamports = 2 * Rent::default().minimum_balance(200);
+    /// let mut input = serialize(&[
+    ///     (payer_key, true, true, 0, vec![], system_key),
+    ///     (account_key, false, true, lamports, vec![0xaa; 200], program_id),
+    ///     (system_key, false, false, 0, vec![], Pubkey::default()),
+    /// ]);
+    /// let (_, accounts, _) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+    /// let (payer, account, system_program) = (&accounts[0], &accounts[1], &accounts[2]);
+    ///
+    /// Processor::resize_account(payer, account, system_program, 100).unwrap();
+    /// assert_eq!(account.data_len(), 100);
+    /// Processor::resize_account(payer, account, system_program, 200).unwrap();
+    ///
+    /// // The old bytes past the shrink don't come back
+    /// let data = account.data.borrow();
+    /// assert!(data[..100].iter().all(|byte| *byte == 0xaa));
+    /// assert!(data[100..].iter().all(|byte| *byte == 0));
+    /// ```
+    pub fn resize_account<'a>(
+        payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+        system_program: &AccountInfo<'a>,
+        new_size: usize,
+    ) -> ProgramResult {
+        let (rent, lamports_required) = rent_and_minimum(new_size)?;
+        let delta = rent_delta(&rent, account.data_len(), new_size);
+        if delta > 0 {
+            // Top up lamports so the account stays rent exempt at its new size
+            let shortfall = lamports_required.saturating_sub(account.lamports());
+            if shortfall > 0 {
+                msg!("Transferring {} lamports to cover rent", shortfall);
+                invoke(
+                    &system_instruction::transfer(payer.key, account.key, shortfall),
+                    &[payer.clone(), account.clone(), system_program.clone()],
+                )?;
+            }
+        } else if delta < 0 {
+            // Refund rent freed by shrinking, keeping the account rent exempt
+            let refund = delta
+                .unsigned_abs()
+                .min(account.lamports().saturating_sub(lamports_required));
+            if refund > 0 {
+                msg!("Refunding {} lamports of rent", refund);
+                let payer_lamports = payer
+                    .lamports()
+                    .checked_add(refund)
+                    .ok_or(ProgramError::InvalidArgument)?;
+                **payer.lamports.borrow_mut() = payer_lamports;
+                **account.lamports.borrow_mut() -= refund;
+            }
+        }
+        
+        // Zero any newly exposed bytes; realloc doesn't clear memory that a
+        // previous shrink in this instruction left behind
+        msg!("Reallocating account to {} bytes", new_size);
+        account.realloc(new_size, true)?;
+        
+        Ok(())
+    }
+}
+
+// Log the remaining compute units, labelled with the instruction and stage
+#[cfg(feature = "profile")]
+fn log_compute_units(variant: &str, stage: &str) {
+    msg!("Profile: {} {}", variant, stage);
+    solana_program::log::sol_log_compute_units();
+}
+
+#[cfg(not(feature = "profile"))]
+#[inline(always)]
+fn log_compute_units(_variant: &str, _stage: &str) {}
+
+/// Lamports owed (positive) or freed (negative) by resizing an account
+/// from `old_size` to `new_size` bytes, keeping it rent exempt
+///
+/// ```
+/// use solana_clean_demo::processor::rent_delta;
+/// use solana_program::rent::Rent;
+///
+/// let rent = Rent::default();
+/// let grow = rent_delta(&rent, 100, 200);
+/// assert_eq!(grow, (rent.minimum_balance(200) - rent.minimum_balance(100)) as i64);
+/// assert!(grow > 0);
+/// assert_eq!(rent_delta(&rent, 200, 100), -grow);
+/// assert_eq!(rent_delta(&rent, 100, 100), 0);
+/// ```
+pub fn rent_delta(rent: &Rent, old_size: usize, new_size: usize) -> i64 {
+    rent.minimum_balance(new_size) as i64 - rent.minimum_balance(old_size) as i64
+}
+
+/// Bytes of account data per DumpRaw log line, keeping lines well short of
+/// the runtime's log limits
+#[cfg(feature = "debug-tools")]
+pub const HEX_LINE_BYTES: usize = 64;
+
+/// Format data as hex lines of `HEX_LINE_BYTES` bytes, each prefixed with
+/// the offset of its first byte
+///
+/// ```
+/// use solana_clean_demo::processor::{hex_lines, HEX_LINE_BYTES};
+///
+/// let lines: Vec<String> = hex_lines(&[0x00, 0x1f, 0xab]).collect();
+/// assert_eq!(lines, vec!["0000: 001fab".to_string()]);
+///
+/// let lines: Vec<String> = hex_lines(&[0xff; HEX_LINE_BYTES + 1]).collect();
+/// assert_eq!(lines.len(), 2);
+/// assert_eq!(lines[1], format!("{:04x}: ff", HEX_LINE_BYTES));
+/// ```
+#[cfg(feature = "debug-tools")]
+pub fn hex_lines(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    data.chunks(HEX_LINE_BYTES).enumerate().map(|(index, chunk)| {
+        let hex: String = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("{:04x}: {}", index * HEX_LINE_BYTES, hex)
+    })
+}
+
+/// Take the next account, logging which one was missing if there are none
+///
+/// ```
+/// use std::sync::Mutex;
+/// use solana_clean_demo::processor::expect_account;
+/// use solana_program::{account_info::AccountInfo, program_error::ProgramError, program_stubs, pubkey::Pubkey};
+///
+/// static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// struct CaptureLogs;
+/// impl program_stubs::SyscallStubs for CaptureLogs {
+///     fn sol_log(&self, message: &str) {
+///         LOGS.lock().unwrap().push(message.to_string());
+///     }
+/// }
+/// program_stubs::set_syscall_stubs(Box::new(CaptureLogs));
+///
+/// let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+/// let (mut lamports, mut data) = (0, vec![]);
+/// let accounts = [AccountInfo::new(&key, true, true, &mut lamports, &mut data, &owner, false, 0)];
+///
+/// // The system program was left out
+/// let accounts_iter = &mut accounts.iter();
+/// assert_eq!(expect_account(accounts_iter, "owner").unwrap().key, &key);
+/// assert_eq!(
+///     expect_account(accounts_iter, "system program").unwrap_err(),
+///     ProgramError::NotEnoughAccountKeys
+/// );
+/// if cfg!(not(feature = "no-logs")) {
+///     assert_eq!(*LOGS.lock().unwrap(), vec!["Missing account: system program".to_string()]);
+/// }
+/// ```
+pub fn expect_account<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    iter: &mut I,
+    name: &str,
+) -> Result<I::Item, ProgramError> {
+    iter.next().ok_or_else(|| {
+        msg!("Missing account: {}", name);
+        ProgramError::NotEnoughAccountKeys
+    })
+}
+
+/// Reject a message that is empty or only whitespace with `BlankMessage`,
+/// when built with the `reject-blank` feature; otherwise accept any message
+///
+/// Applies to the main message, as set by Initialize, the UpdateMessage
+/// variants and SwapMessage. Slots may still be cleared with an empty string.
+///
+/// ```
+/// use solana_clean_demo::processor::check_message_not_blank;
+///
+/// for blank in ["", " ", " \t\n "] {
+///     assert_eq!(check_message_not_blank(blank).is_err(), cfg!(feature = "reject-blank"));
+/// }
+/// assert!(check_message_not_blank(" Hello Solana! ").is_ok());
+/// ```
+pub fn check_message_not_blank(message: &str) -> ProgramResult {
+    if cfg!(feature = "reject-blank") && message.trim().is_empty() {
+        msg!("Error: Message is blank");
+        return Err(AccountDemoError::BlankMessage.into());
+    }
+    Ok(())
+}
+
+/// Fetch the rent sysvar once, returning it with the rent-exempt minimum
+/// for `data_len` bytes
+///
+/// ```
+/// use solana_clean_demo::processor::rent_and_minimum;
+/// use solana_program::{program_stubs, rent::Rent};
+///
+/// struct DefaultRent;
+/// impl program_stubs::SyscallStubs for DefaultRent {
+///     fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+///         unsafe { *(var_addr as *mut Rent) = Rent::default() };
+///         0
+///     }
+/// }
+/// program_stubs::set_syscall_stubs(Box::new(DefaultRent));
+///
+/// let (rent, minimum) = rent_and_minimum(200).unwrap();
+/// assert_eq!(rent, Rent::default());
+/// assert_eq!(minimum, Rent::default().minimum_balance(200));
+/// ```
+pub fn rent_and_minimum(data_len: usize) -> Result<(Rent, u64), ProgramError> {
+    let rent = Rent::get()?;
+    let minimum = rent.minimum_balance(data_len);
+    Ok((rent, minimum))
+}
+
+/// Balance left after withdrawing `amount` from an account holding
+/// `lamports`, if it stays at or above the rent-exempt `minimum`
+///
+/// ```
+/// use solana_clean_demo::{error::AccountDemoError, processor::check_withdrawal};
+/// use solana_program::{program_error::ProgramError, rent::Rent};
+///
+/// let minimum = Rent::default().minimum_balance(200);
+///
+/// assert_eq!(check_withdrawal(minimum + 500, minimum, 500), Ok(minimum));
+/// assert_eq!(
+///     check_withdrawal(minimum + 500, minimum, 501),
+///     Err(ProgramError::from(AccountDemoError::WouldBreakRentExemption))
+/// );
+/// ```
+pub fn check_withdrawal(lamports: u64, minimum: u64, amount: u64) -> Result<u64, ProgramError> {
+    lamports
+        .checked_sub(amount)
+        .filter(|remaining| *remaining >= minimum)
+        .ok_or_else(|| AccountDemoError::WouldBreakRentExemption.into())
+}
+
+/// Load UserData for an instruction the owner signs to change it
+///
+/// `owner` must sign and be the stored owner, and `account` must be a
+/// different, non-executable account owned by the program.
+pub fn load_owned_user_data(
+    program_id: &Pubkey,
+    owner: &AccountInfo,
+    account: &AccountInfo,
+) -> Result<UserData, ProgramError> {
+    if !owner.is_signer {
+        msg!("Error: User is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    
+    // The signer and the data account must be different accounts
+    if owner.key == account.key {
+        msg!("Error: Signer and data account are the same account");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    if account.owner != program_id {
+        msg!("Error: Account is not owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    
+    // Executable accounts can never hold UserData
+    check_not_executable(account, "user data")?;
+    
+    let user_data = UserData::load_initialized(&account.data.borrow())?;
+    if !pubkeys_eq_ct(&user_data.get_owner(), owner.key) {
+        msg!("Error: User is not the account owner");
+        return Err(AccountDemoError::NotOwner.into());
+    }
+    
+    Ok(user_data)
+}
+
+/// Check that `config` is the program's Config PDA
+///
+/// Until InitConfig creates it the account is empty, and any owner is
+/// accepted; once it holds data the program must own it.
+pub fn check_config_account(program_id: &Pubkey, config: &AccountInfo) -> ProgramResult {
+    let (config_address, _) = pda::find_config_address(program_id);
+    if *config.key != config_address {
+        msg!("Error: Account is not the program config");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !config.data_is_empty() && config.owner != program_id {
+        msg!("Error: Config is not owned by this program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// Load the program Config for an instruction only its admin may run
+///
+/// `admin` must sign and be the admin InitConfig stored in `config`.
+pub fn load_admin_config(
+    program_id: &Pubkey,
+    admin: &AccountInfo,
+    config: &AccountInfo,
+) -> Result<Config, ProgramError> {
+    check_config_account(program_id, config)?;
+    let loaded = Config::load_initialized(&config.data.borrow())?;
+    
+    if !admin.is_signer {
+        msg!("Error: Admin is not a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !pubkeys_eq_ct(admin.key, &Pubkey::new_from_array(loaded.admin)) {
+        msg!("Error: Signer is not the program admin");
+        return Err(AccountDemoError::NotOwner.into());
+    }
+    
+    Ok(loaded)
+}
+
+/// Load UserData for a read-only instruction
+///
+/// With `expected_owner`, the account must also be owned by the program
+/// and be the canonical PDA of that owner, who must be the stored owner,
+/// so a look-alike account can't be passed off as the owner's. Accounts
+/// with a verified bump are checked against it instead of searching for
+/// the bump again.
+pub fn load_user_data(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    expected_owner: Option<&Pubkey>,
+) -> Result<UserData, ProgramError> {
+    let user_data = UserData::load_initialized(&account.data.borrow())?;
+    
+    if let Some(expected_owner) = expected_owner {
+        if account.owner != program_id {
+            msg!("Error: Account is not owned by this program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if !user_data.is_owner(expected_owner) {
+            msg!("Error: Account is not owned by the expected owner");
+            return Err(AccountDemoError::NotOwner.into());
+        }
+        if user_data_address(program_id, expected_owner, &user_data)? != *account.key {
+            msg!("Error: Account is not the owner's PDA");
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    
+    Ok(user_data)
+}
+
+/// Check that `account` is the owner's UserData PDA at its verified bump
+///
+/// Closing an account or moving its lamports is only allowed once
+/// Initialize or CanonicalizePda has set `FLAG_CANONICAL`; accounts from
+/// before then fail with `InvalidPda` until CanonicalizePda runs. The
+/// stored bump must also still be the canonical one, so an account at
+/// another bump of the same seeds can't stand in for it.
+pub fn check_canonical_pda(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    user_data: &UserData,
+) -> ProgramResult {
+    if !user_data.is_canonical() {
+        msg!("Error: Account has no verified bump, run CanonicalizePda first");
+        return Err(AccountDemoError::InvalidPda.into());
+    }
+    pda::assert_canonical_bump(program_id, &user_data.get_owner(), user_data.bump)?;
+    if user_data_address(program_id, &user_data.get_owner(), user_data)? != *account.key {
+        msg!("Error: Account is not the owner's PDA");
+        return Err(AccountDemoError::InvalidPda.into());
+    }
+    Ok(())
+}
+
+// The owner's UserData PDA, from the stored bump once it has been verified,
+// otherwise by searching for the canonical bump
+fn user_data_address(program_id: &Pubkey, owner: &Pubkey, user_data: &UserData) -> Result<Pubkey, ProgramError> {
+    if !user_data.is_canonical() {
+        return Ok(pda::find_user_data_address(owner, program_id).0);
+    }
+    let bump_seed = [user_data.bump];
+    Pubkey::create_program_address(&pda::signer_seeds(owner, &bump_seed), program_id)
+        .map_err(|_| AccountDemoError::InvalidPda.into())
+}
+
+/// Reject an executable account passed where UserData will be written
+///
+/// ```
+/// use borsh::BorshSerialize;
+/// use solana_clean_demo::{entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData};
+/// use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+///
+/// let program_id = Pubkey::new_unique();
+/// let (config_key, _) = pda::find_config_address(&program_id);
+/// let (owner_key, data_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+/// let system_key = solana_program::system_program::id();
+///
+/// let mut user_data = UserData::new(owner_key, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+/// let mut data_data = vec![0u8; user_data.size_of_current()];
+/// user_data.save(&mut data_data).unwrap();
+///
+/// let (mut owner_lamports, mut data_lamports, mut config_lamports) = (0, 1_000_000, 0);
+/// let (mut owner_data, mut config_data) = (vec![], vec![]);
+/// let owner = AccountInfo::new(&owner_key, true, false, &mut owner_lamports, &mut owner_data, &system_key, false, 0);
+/// let data = AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, true, 0);
+/// let config = AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0);
+///
+/// let update = AccountDemoInstruction::UpdateMessage { message: "hi".to_string() }.try_to_vec().unwrap();
+/// assert_eq!(
+///     process_instruction(&program_id, &[owner, data, config], &update).unwrap_err(),
+///     ProgramError::InvalidArgument
+/// );
+/// ```
+pub fn check_not_executable(account: &AccountInfo, name: &str) -> ProgramResult {
+    if account.executable {
+        msg!("Error: The {} account is executable", name);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Check that `authority` is the upgrade authority recorded in the
+/// program's ProgramData account
+///
+/// A program deployed without an upgrade authority, or with a
+/// non-upgradeable loader, has no one who can pass this check.
+pub fn check_upgrade_authority(
+    program_id: &Pubkey,
+    program_data_account: &AccountInfo,
+    authority: &Pubkey,
+) -> ProgramResult {
+    if *program_data_account.key != pda::find_program_data_address(program_id)
+        || *program_data_account.owner != bpf_loader_upgradeable::id()
+    {
+        msg!("Error: Account is not the program's ProgramData");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    let state: UpgradeableLoaderState = limited_deserialize(
+        &program_data_account.data.borrow(),
+        UpgradeableLoaderState::size_of_programdata_metadata() as u64,
+    )
+    .map_err(|_| AccountDemoError::DataTypeMismatch)?;
+    match state {
+        UpgradeableLoaderState::ProgramData { upgrade_authority_address: Some(upgrade_authority), .. }
+            if pubkeys_eq_ct(&upgrade_authority, authority) =>
+        {
+            Ok(())
+        }
+        _ => {
+            msg!("Error: Signer is not the program upgrade authority");
+            Err(AccountDemoError::NotOwner.into())
+        }
+    }
+}
+
+/// Most bytes of instruction data logged before truncating
+pub const MAX_LOGGED_BYTES: usize = 64;
+
+/// Format at most `max` bytes of `data`, marking any cut with an ellipsis
+/// and the full length
+///
+/// ```
+/// use solana_clean_demo::processor::truncated_bytes;
+///
+/// assert_eq!(truncated_bytes(&[1, 2, 3], 4), "[1, 2, 3]");
+/// assert_eq!(truncated_bytes(&[7; 1000], 3), "[7, 7, 7, ...] (1000 bytes)");
+/// assert_eq!(truncated_bytes(&[7; 2], 0), "[...] (2 bytes)");
+/// ```
+pub fn truncated_bytes(data: &[u8], max: usize) -> String {
+    if data.len() <= max {
+        return format!("{:?}", data);
+    }
+    let mut shown: Vec<String> = data[..max].iter().map(u8::to_string).collect();
+    shown.push("...".to_string());
+    format!("[{}] ({} bytes)", shown.join(", "), data.len())
 }
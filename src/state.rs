@@ -1,60 +1,1233 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use constant_time_eq::constant_time_eq;
+use std::convert::TryInto;
+use solana_program::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE, program_error::ProgramError, pubkey::Pubkey,
+};
 use crate::error::AccountDemoError;
 
 /// Data structure stored in the Solana account
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub struct UserData {
+    pub flags: u8,  // Bitfield of the FLAG_* constants
+    pub owner: OwnerKey,
+    pub name: String,
+    pub message: String,
+    pub update_count: u64,
+    pub messages: Vec<String>,  // Additional messages keyed by slot index
+    pub alias: String,  // Display handle, does not affect the PDA
+    pub last_update_slot: u64,  // Slot of the last UpdateMessage, 0 if never updated
+    pub checksum: u32,  // CRC32 of name + message, refreshed on every save
+    pub avatar_hash: Option<[u8; 32]>,  // Hash of an off-chain avatar, 1 byte when unset
+    pub label: String,  // Free-form display text for UIs, no on-chain meaning
+    pub name_updated_at: i64,  // Unix timestamp the name was last set, 0 if unknown
+    pub message_updated_at: i64,  // Unix timestamp the message was last set, 0 if unknown
+    pub bump: u8,  // Canonical PDA bump, valid only while FLAG_CANONICAL is set
+}
+
+/// Owner of a UserData account, stored as the raw key bytes
+///
+/// Deliberately not `PartialEq`: compare with `ct_eq`, which runs in
+/// constant time.
+///
+/// ```
+/// use solana_clean_demo::state::OwnerKey;
+/// use solana_program::pubkey::Pubkey;
+///
+/// let key = Pubkey::new_unique();
+/// let owner = OwnerKey::from(key);
+/// assert_eq!(owner.0, key.to_bytes());
+/// assert_eq!(Pubkey::from(owner), key);
+///
+/// assert!(owner.ct_eq(&key));
+/// assert!(!owner.ct_eq(&Pubkey::new_unique()));
+/// ```
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+pub struct OwnerKey(pub [u8; 32]);
+
+impl OwnerKey {
+    /// Whether this is `key`, compared in constant time
+    pub fn ct_eq(&self, key: &Pubkey) -> bool {
+        constant_time_eq(&self.0, key.as_ref())
+    }
+}
+
+impl From<Pubkey> for OwnerKey {
+    fn from(key: Pubkey) -> Self {
+        OwnerKey(key.to_bytes())
+    }
+}
+
+impl From<OwnerKey> for Pubkey {
+    fn from(owner: OwnerKey) -> Self {
+        Pubkey::new_from_array(owner.0)
+    }
+}
+
+/// Fixed-size fields of a UserData account in a flat `#[repr(C)]` layout,
+/// for bindings from C or TypeScript that can't parse Borsh
+///
+/// Borsh stays the on-chain format; this is only a view for FFI. Fields
+/// run from the widest down so there is no interior padding, and
+/// `reserved` makes the trailing padding explicit. Whether the account is
+/// initialized is the `FLAG_INITIALIZED` bit of `flags`.
+///
+/// ```
+/// use std::mem::{align_of, size_of};
+/// use solana_clean_demo::state::UserDataHeader;
+///
+/// assert_eq!(size_of::<UserDataHeader>(), 104);
+/// assert_eq!(align_of::<UserDataHeader>(), 8);
+/// ```
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UserDataHeader {
+    pub update_count: u64,
+    pub last_update_slot: u64,
+    pub name_updated_at: i64,
+    pub message_updated_at: i64,
+    pub checksum: u32,
+    pub owner: [u8; 32],
+    pub avatar_hash: [u8; 32],  // All zero when unset
+    pub flags: u8,
+    pub has_avatar_hash: u8,  // 1 if avatar_hash is set, else 0
+    pub bump: u8,
+    pub reserved: [u8; 1],  // Always zero
+}
+
+/// Variable-length fields of a UserData account, split off its header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDataStrings {
+    pub name: String,
+    pub message: String,
+    pub messages: Vec<String>,
+    pub alias: String,
+    pub label: String,
+}
+
+/// Original account layout, before slots, alias, rate limiting and checksums
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct UserDataV1 {
     pub is_initialized: bool,
-    pub owner: [u8; 32],  // Store Pubkey as a byte array
+    pub owner: [u8; 32],
     pub name: String,
     pub message: String,
     pub update_count: u64,
 }
 
+/// Account layout before the avatar hash
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct UserDataV2 {
+    pub flags: u8,
+    pub owner: OwnerKey,
+    pub name: String,
+    pub message: String,
+    pub update_count: u64,
+    pub messages: Vec<String>,
+    pub alias: String,
+    pub last_update_slot: u64,
+    pub checksum: u32,
+}
+
+/// Account layout before the label
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct UserDataV3 {
+    pub flags: u8,
+    pub owner: OwnerKey,
+    pub name: String,
+    pub message: String,
+    pub update_count: u64,
+    pub messages: Vec<String>,
+    pub alias: String,
+    pub last_update_slot: u64,
+    pub checksum: u32,
+    pub avatar_hash: Option<[u8; 32]>,
+}
+
+/// Account layout before the per-field timestamps
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct UserDataV4 {
+    pub flags: u8,
+    pub owner: OwnerKey,
+    pub name: String,
+    pub message: String,
+    pub update_count: u64,
+    pub messages: Vec<String>,
+    pub alias: String,
+    pub last_update_slot: u64,
+    pub checksum: u32,
+    pub avatar_hash: Option<[u8; 32]>,
+    pub label: String,
+}
+
+/// Account layout before the stored PDA bump
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct UserDataV5 {
+    pub flags: u8,
+    pub owner: OwnerKey,
+    pub name: String,
+    pub message: String,
+    pub update_count: u64,
+    pub messages: Vec<String>,
+    pub alias: String,
+    pub last_update_slot: u64,
+    pub checksum: u32,
+    pub avatar_hash: Option<[u8; 32]>,
+    pub label: String,
+    pub name_updated_at: i64,
+    pub message_updated_at: i64,
+}
+
+/// Versioned wrapper for the account data
+///
+/// The Borsh variant tag doubles as the layout version, so loading can
+/// dispatch on it and a future layout only needs a new arm. Variants must
+/// never be reordered.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub enum StoredUserData {
+    V1(UserDataV1),
+    V2(UserDataV2),
+    V3(UserDataV3),
+    V4(UserDataV4),
+    V5(UserDataV5),
+    V6(UserData),
+}
+
+impl StoredUserData {
+    /// Read account data of any layout, including data written before the
+    /// layouts were tagged
+    ///
+    /// Untagged data starts with the original `is_initialized` byte, 1 for
+    /// any live account, which Borsh would take for the V2 tag. So data
+    /// starting with 1 is only read as V2 if its checksum matches its name
+    /// and message. Otherwise it is read as the untagged original layout,
+    /// provided only zero padding follows it.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::{StoredUserData, UserData};
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// // An account as the original program wrote it, with no variant tag
+    /// let owner = Pubkey::new_unique();
+    /// let mut data = vec![1];
+    /// data.extend_from_slice(owner.as_ref());
+    /// data.extend_from_slice(&8u32.to_le_bytes());
+    /// data.extend_from_slice(b"John Doe");
+    /// data.extend_from_slice(&2u32.to_le_bytes());
+    /// data.extend_from_slice(b"Hi");
+    /// data.extend_from_slice(&3u64.to_le_bytes());
+    /// assert!(matches!(StoredUserData::from_account_data(&data).unwrap(), StoredUserData::V1(_)));
+    ///
+    /// // An account in the current layout keeps its own
+    /// let mut user_data = UserData::new(owner, "John Doe".to_string(), "Hi".to_string()).unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data).unwrap();
+    /// assert!(matches!(StoredUserData::from_account_data(&data).unwrap(), StoredUserData::V6(_)));
+    /// ```
+    pub fn from_account_data(data: &[u8]) -> Result<Self, ProgramError> {
+        let tagged = Self::deserialize(&mut &data[..]).map_err(|_| AccountDemoError::DataTypeMismatch);
+        if data.first() != Some(&1) {
+            return Ok(tagged?);
+        }
+        
+        // Tagged V2 data carries a checksum of its own name and message
+        if let Ok(StoredUserData::V2(v2)) = &tagged {
+            if v2.checksum == crc32(&[v2.name.as_bytes(), v2.message.as_bytes()]) {
+                return Ok(tagged?);
+            }
+        }
+        
+        // The original layout, followed by nothing but zero padding
+        let mut rest = data;
+        if let Ok(v1) = UserDataV1::deserialize(&mut rest) {
+            if rest.iter().all(|byte| *byte == 0) {
+                return Ok(StoredUserData::V1(v1));
+            }
+        }
+        
+        // Corrupt V2 data, left for the caller's checksum check to reject
+        Ok(tagged?)
+    }
+
+    /// Upgrade any stored version to the current `UserData` layout
+    ///
+    /// ```
+    /// use borsh::{BorshDeserialize, BorshSerialize};
+    /// use solana_clean_demo::state::{OwnerKey, StoredUserData, UserData, UserDataV2, UserDataV3, UserDataV4, UserDataV5};
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// // The original layout, which was stored without a variant tag
+    /// let mut blob = vec![1];
+    /// blob.extend_from_slice(Pubkey::new_unique().as_ref());
+    /// blob.extend_from_slice(&8u32.to_le_bytes());
+    /// blob.extend_from_slice(b"John Doe");
+    /// blob.extend_from_slice(&13u32.to_le_bytes());
+    /// blob.extend_from_slice(b"Hello Solana!");
+    /// blob.extend_from_slice(&3u64.to_le_bytes());
+    /// let user_data = StoredUserData::from_account_data(&blob).unwrap().into_latest();
+    /// assert_eq!(user_data.update_count, 3);
+    /// assert!(user_data.messages.is_empty());
+    /// assert_eq!(user_data.checksum, user_data.compute_checksum());
+    ///
+    /// let v2 = StoredUserData::V2(UserDataV2 {
+    ///     flags: UserData::FLAG_INITIALIZED,
+    ///     owner: OwnerKey(Pubkey::new_unique().to_bytes()),
+    ///     name: "Jane".to_string(),
+    ///     message: "Hi".to_string(),
+    ///     update_count: 2,
+    ///     messages: vec!["slot".to_string()],
+    ///     alias: "jane".to_string(),
+    ///     last_update_slot: 7,
+    ///     checksum: 0,
+    /// });
+    /// let blob = v2.try_to_vec().unwrap();
+    /// let user_data = StoredUserData::try_from_slice(&blob).unwrap().into_latest();
+    /// assert_eq!((user_data.alias.as_str(), user_data.last_update_slot), ("jane", 7));
+    /// assert_eq!(user_data.avatar_hash, None);
+    ///
+    /// let v3 = StoredUserData::V3(UserDataV3 {
+    ///     flags: UserData::FLAG_INITIALIZED,
+    ///     owner: OwnerKey(Pubkey::new_unique().to_bytes()),
+    ///     name: "Jane".to_string(),
+    ///     message: "Hi".to_string(),
+    ///     update_count: 2,
+    ///     messages: vec![],
+    ///     alias: String::new(),
+    ///     last_update_slot: 0,
+    ///     checksum: 0,
+    ///     avatar_hash: Some([7; 32]),
+    /// });
+    /// let blob = v3.try_to_vec().unwrap();
+    /// let user_data = StoredUserData::try_from_slice(&blob).unwrap().into_latest();
+    /// assert_eq!(user_data.avatar_hash, Some([7; 32]));
+    /// assert_eq!(user_data.label, "");
+    ///
+    /// let v4 = StoredUserData::V4(UserDataV4 {
+    ///     flags: UserData::FLAG_INITIALIZED,
+    ///     owner: OwnerKey(Pubkey::new_unique().to_bytes()),
+    ///     name: "Jane".to_string(),
+    ///     message: "Hi".to_string(),
+    ///     update_count: 2,
+    ///     messages: vec![],
+    ///     alias: String::new(),
+    ///     last_update_slot: 0,
+    ///     checksum: 0,
+    ///     avatar_hash: None,
+    ///     label: "Work".to_string(),
+    /// });
+    /// let blob = v4.try_to_vec().unwrap();
+    /// let user_data = StoredUserData::try_from_slice(&blob).unwrap().into_latest();
+    /// assert_eq!(user_data.label, "Work");
+    /// assert_eq!((user_data.name_updated_at, user_data.message_updated_at), (0, 0));
+    ///
+    /// let v5 = StoredUserData::V5(UserDataV5 {
+    ///     flags: UserData::FLAG_INITIALIZED,
+    ///     owner: OwnerKey(Pubkey::new_unique().to_bytes()),
+    ///     name: "Jane".to_string(),
+    ///     message: "Hi".to_string(),
+    ///     update_count: 2,
+    ///     messages: vec![],
+    ///     alias: String::new(),
+    ///     last_update_slot: 0,
+    ///     checksum: 0,
+    ///     avatar_hash: None,
+    ///     label: String::new(),
+    ///     name_updated_at: 1_700_000_000,
+    ///     message_updated_at: 1_700_000_000,
+    /// });
+    /// let blob = v5.try_to_vec().unwrap();
+    /// let user_data = StoredUserData::try_from_slice(&blob).unwrap().into_latest();
+    /// assert_eq!(user_data.name_updated_at, 1_700_000_000);
+    /// assert!(!user_data.is_canonical());
+    ///
+    /// let v6 = StoredUserData::V6(UserData::new(Pubkey::new_unique(), "Jane".to_string(), "Hi".to_string()).unwrap());
+    /// let blob = v6.try_to_vec().unwrap();
+    /// assert_eq!(StoredUserData::try_from_slice(&blob).unwrap().into_latest().name, "Jane");
+    /// ```
+    pub fn into_latest(self) -> UserData {
+        match self {
+            StoredUserData::V1(v1) => {
+                let mut user_data = UserData {
+                    flags: if v1.is_initialized { UserData::FLAG_INITIALIZED } else { 0 },
+                    owner: OwnerKey(v1.owner),
+                    name: v1.name,
+                    message: v1.message,
+                    update_count: v1.update_count,
+                    messages: Vec::new(),
+                    alias: String::new(),
+                    last_update_slot: 0,
+                    checksum: 0,
+                    avatar_hash: None,
+                    label: String::new(),
+                    name_updated_at: 0,
+                    message_updated_at: 0,
+                    bump: 0,
+                };
+                user_data.checksum = user_data.compute_checksum();
+                user_data
+            }
+            StoredUserData::V2(v2) => UserData {
+                flags: v2.flags,
+                owner: v2.owner,
+                name: v2.name,
+                message: v2.message,
+                update_count: v2.update_count,
+                messages: v2.messages,
+                alias: v2.alias,
+                last_update_slot: v2.last_update_slot,
+                checksum: v2.checksum,
+                avatar_hash: None,
+                label: String::new(),
+                name_updated_at: 0,
+                message_updated_at: 0,
+                bump: 0,
+            },
+            StoredUserData::V3(v3) => UserData {
+                flags: v3.flags,
+                owner: v3.owner,
+                name: v3.name,
+                message: v3.message,
+                update_count: v3.update_count,
+                messages: v3.messages,
+                alias: v3.alias,
+                last_update_slot: v3.last_update_slot,
+                checksum: v3.checksum,
+                avatar_hash: v3.avatar_hash,
+                label: String::new(),
+                name_updated_at: 0,
+                message_updated_at: 0,
+                bump: 0,
+            },
+            StoredUserData::V4(v4) => UserData {
+                flags: v4.flags,
+                owner: v4.owner,
+                name: v4.name,
+                message: v4.message,
+                update_count: v4.update_count,
+                messages: v4.messages,
+                alias: v4.alias,
+                last_update_slot: v4.last_update_slot,
+                checksum: v4.checksum,
+                avatar_hash: v4.avatar_hash,
+                label: v4.label,
+                name_updated_at: 0,
+                message_updated_at: 0,
+                bump: 0,
+            },
+            StoredUserData::V5(v5) => UserData {
+                flags: v5.flags,
+                owner: v5.owner,
+                name: v5.name,
+                message: v5.message,
+                update_count: v5.update_count,
+                messages: v5.messages,
+                alias: v5.alias,
+                last_update_slot: v5.last_update_slot,
+                checksum: v5.checksum,
+                avatar_hash: v5.avatar_hash,
+                label: v5.label,
+                name_updated_at: v5.name_updated_at,
+                message_updated_at: v5.message_updated_at,
+                bump: 0,
+            },
+            StoredUserData::V6(user_data) => user_data,
+        }
+    }
+}
+
+/// Program-wide settings, stored in the Config PDA
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub is_initialized: bool,
+    pub admin: [u8; 32],  // Key allowed to change the settings, chosen by InitConfig
+    pub paused: bool,  // When set, every mutating instruction fails
+    pub attester: [u8; 32],  // Key besides the owner trusted to sign messages, all zero for none
+}
+
+impl Config {
+    pub const LEN: usize = 1 + 32 + 1 + 32;
+
+    /// Whether mutating instructions are blocked by this account data
+    ///
+    /// A Config PDA that was never created has empty data and is not paused.
+    ///
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::state::Config;
+    ///
+    /// assert!(!Config::is_paused(&[]).unwrap());
+    ///
+    /// let config = Config { is_initialized: true, admin: [7; 32], paused: true, attester: [0; 32] };
+    /// assert!(Config::is_paused(&config.try_to_vec().unwrap()).unwrap());
+    /// ```
+    pub fn is_paused(data: &[u8]) -> Result<bool, ProgramError> {
+        if data.is_empty() {
+            return Ok(false);
+        }
+        let config = Self::deserialize(&mut &data[..])
+            .map_err(|_| AccountDemoError::DataTypeMismatch)?;
+        Ok(config.is_initialized && config.paused)
+    }
+
+    /// The attester stored in this account data, if one is configured
+    ///
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::state::Config;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// assert_eq!(Config::attester(&[]).unwrap(), None);
+    ///
+    /// let mut config = Config { is_initialized: true, admin: [7; 32], paused: false, attester: [0; 32] };
+    /// assert_eq!(Config::attester(&config.try_to_vec().unwrap()).unwrap(), None);
+    ///
+    /// config.attester = [9; 32];
+    /// assert_eq!(Config::attester(&config.try_to_vec().unwrap()).unwrap(), Some(Pubkey::new_from_array([9; 32])));
+    /// ```
+    pub fn attester(data: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+        if data.is_empty() {
+            return Ok(None);
+        }
+        let config = Self::deserialize(&mut &data[..])
+            .map_err(|_| AccountDemoError::DataTypeMismatch)?;
+        if !config.is_initialized || config.attester == [0; 32] {
+            return Ok(None);
+        }
+        Ok(Some(Pubkey::new_from_array(config.attester)))
+    }
+
+    /// Deserialize a Config that InitConfig has created
+    ///
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::{error::AccountDemoError, state::Config};
+    /// use solana_program::program_error::ProgramError;
+    ///
+    /// assert_eq!(Config::load_initialized(&[]).unwrap_err(), ProgramError::from(AccountDemoError::NotInitialized));
+    ///
+    /// let config = Config { is_initialized: true, admin: [7; 32], paused: false, attester: [0; 32] };
+    /// assert_eq!(Config::load_initialized(&config.try_to_vec().unwrap()).unwrap(), config);
+    /// ```
+    pub fn load_initialized(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.is_empty() {
+            return Err(AccountDemoError::NotInitialized.into());
+        }
+        let config = Self::deserialize(&mut &data[..])
+            .map_err(|_| AccountDemoError::DataTypeMismatch)?;
+        if !config.is_initialized {
+            return Err(AccountDemoError::NotInitialized.into());
+        }
+        Ok(config)
+    }
+}
+
+/// Scalar fields of a UserData account, returned by GetSummary
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub update_count: u64,
+    pub name_len: u32,
+    pub message_len: u32,
+}
+
 impl UserData {
     pub const MAX_NAME_LENGTH: usize = 64;
     pub const MAX_MESSAGE_LENGTH: usize = 256;
+    pub const MAX_SLOTS: usize = 4;
+    pub const MAX_TOTAL_MESSAGES_LENGTH: usize = 512;
+    pub const MAX_ALIAS_LENGTH: usize = 32;
+    pub const MAX_LABEL_LENGTH: usize = 64;
+    pub const MIN_SLOT_GAP: u64 = 5;
+    /// Largest account the program creates, the most a CPI may allocate
+    pub const MAX_ACCOUNT_SIZE: usize = MAX_PERMITTED_DATA_INCREASE;
+    
+    /// Set once the account has been initialized
+    pub const FLAG_INITIALIZED: u8 = 1 << 0;
+    /// Set by the owner to mark the account as frozen
+    pub const FLAG_FROZEN: u8 = 1 << 1;
+    /// Set by the owner to mark the messages as compressed
+    pub const FLAG_COMPRESSED: u8 = 1 << 2;
+    /// Set while the message carries a verified ed25519 attestation
+    pub const FLAG_VERIFIED: u8 = 1 << 3;
+    /// Set once the account is known to be its owner's canonical PDA, with
+    /// the bump stored in `bump`
+    pub const FLAG_CANONICAL: u8 = 1 << 4;
+    /// Flags the owner may change with SetFlags
+    pub const OWNER_FLAGS: u8 = Self::FLAG_FROZEN | Self::FLAG_COMPRESSED;
     
+    /// Serialized size of a new account holding `name` and `message`, with
+    /// every other field empty
+    ///
+    /// The size is added up field by field. With the `size-exact` dev
+    /// feature it is instead measured with `serialized_size`, and debug
+    /// builds assert that the two agree, so a layout change that forgets
+    /// to update the sum fails every test that sizes an account.
     pub fn get_size(name: &str, message: &str) -> usize {
-        1 +    // is_initialized: bool (1 byte)
-        32 +   // owner: [u8; 32] (32 bytes)
+        #[cfg(feature = "size-exact")]
+        {
+            let size = Self::serialized_size(name, message);
+            debug_assert_eq!(size, Self::summed_size(name, message), "get_size is out of date");
+            size
+        }
+        #[cfg(not(feature = "size-exact"))]
+        Self::summed_size(name, message)
+    }
+
+    /// Serialized size of a new account holding `name` and `message`,
+    /// measured by serializing a throwaway UserData
+    ///
+    /// Allocates, so on-chain code should use `get_size`.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    ///
+    /// assert_eq!(UserData::serialized_size("John Doe", "Hi"), UserData::get_size("John Doe", "Hi"));
+    /// ```
+    pub fn serialized_size(name: &str, message: &str) -> usize {
+        let user_data = UserData {
+            flags: 0,
+            owner: OwnerKey([0; 32]),
+            name: name.to_string(),
+            message: message.to_string(),
+            update_count: 0,
+            messages: Vec::new(),
+            alias: String::new(),
+            last_update_slot: 0,
+            checksum: 0,
+            avatar_hash: None,
+            label: String::new(),
+            name_updated_at: 0,
+            message_updated_at: 0,
+            bump: 0,
+        };
+        // Writing to a Vec can't fail
+        StoredUserData::V6(user_data).try_to_vec().unwrap().len()
+    }
+
+    fn summed_size(name: &str, message: &str) -> usize {
+        1 +    // version: StoredUserData variant tag (1 byte)
+        1 +    // flags: u8 (1 byte)
+        32 +   // owner: OwnerKey (32 bytes)
         4 +    // name length: String length prefix (4 bytes)
         name.len() +  // name content
         4 +    // message length: String length prefix (4 bytes)
         message.len() +  // message content
-        8      // update_count: u64 (8 bytes)
+        8 +    // update_count: u64 (8 bytes)
+        4 +    // messages length: Vec length prefix (4 bytes), slots start empty
+        4 +    // alias length: String length prefix (4 bytes), alias starts empty
+        8 +    // last_update_slot: u64 (8 bytes)
+        4 +    // checksum: u32 (4 bytes)
+        1 +    // avatar_hash: Option tag (1 byte), starts unset
+        4 +    // label length: String length prefix (4 bytes), label starts empty
+        8 +    // name_updated_at: i64 (8 bytes)
+        8 +    // message_updated_at: i64 (8 bytes)
+        1      // bump: u8 (1 byte)
+    }
+
+    /// Size of a new account with `reserve_bytes` of headroom past its data
+    ///
+    /// The reserve lets later, larger writes fit without a realloc.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let size = UserData::reserved_size("John Doe", "Hi", 64).unwrap();
+    /// assert_eq!(size, UserData::get_size("John Doe", "Hi") + 64);
+    ///
+    /// // A longer message later still fits in the reserved space
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hi".to_string()).unwrap();
+    /// user_data.message = "m".repeat(60);
+    /// assert!(user_data.size_of_current() <= size);
+    ///
+    /// assert!(UserData::reserved_size("John Doe", "Hi", UserData::MAX_ACCOUNT_SIZE as u32).is_err());
+    /// ```
+    pub fn reserved_size(name: &str, message: &str, reserve_bytes: u32) -> Result<usize, ProgramError> {
+        Self::get_size(name, message)
+            .checked_add(reserve_bytes as usize)
+            .filter(|size| *size <= Self::MAX_ACCOUNT_SIZE)
+            .ok_or_else(|| AccountDemoError::AccountTooLarge.into())
+    }
+
+    pub fn messages_size(messages: &[String]) -> usize {
+        // Each slot is a String: length prefix (4 bytes) + content
+        messages.iter().map(|message| 4 + message.len()).sum()
     }
     
+    /// Serialized size of this account data, including slots, alias,
+    /// avatar hash and label
+    ///
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::state::{StoredUserData, UserData};
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// user_data.set_message_at(1, "second slot".to_string()).unwrap();
+    /// user_data.set_alias("johnd".to_string()).unwrap();
+    /// user_data.avatar_hash = Some([7; 32]);
+    /// user_data.set_label("Work".to_string()).unwrap();
+    ///
+    /// let stored = StoredUserData::V6(user_data.clone());
+    /// assert_eq!(user_data.size_of_current(), stored.try_to_vec().unwrap().len());
+    /// ```
+    pub fn size_of_current(&self) -> usize {
+        Self::get_size(&self.name, &self.message)
+            + Self::messages_size(&self.messages)
+            + self.alias.len()
+            + self.avatar_hash.map_or(0, |hash| hash.len())
+            + self.label.len()
+    }
+    
+    /// Create initialized account data for `owner`
+    ///
+    /// With the `sanitize-names` feature, names containing control
+    /// characters (such as a newline that could forge extra log lines) are
+    /// rejected with `InvalidName`.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let result = UserData::new(Pubkey::new_unique(), "John\nDoe".to_string(), String::new());
+    /// assert_eq!(result.is_err(), cfg!(feature = "sanitize-names"));
+    /// ```
     pub fn new(owner: Pubkey, name: String, message: String) -> Result<Self, ProgramError> {
+        Self::construct(owner, name, message, 1, true)
+    }
+    
+    /// Build account data from explicit field values, for off-chain
+    /// simulators and tests that need a specific state
+    ///
+    /// Applies the same validation as `new`; the remaining fields start
+    /// empty, as they do for a freshly initialized account.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let owner = Pubkey::new_unique();
+    /// let mut user_data = UserData::construct(owner, "John Doe".to_string(), "Hello Solana!".to_string(), 42, true).unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data).unwrap();
+    ///
+    /// let loaded = UserData::load_initialized(&data).unwrap();
+    /// assert_eq!(loaded.get_owner(), owner);
+    /// assert_eq!(loaded.name, "John Doe");
+    /// assert_eq!(loaded.message, "Hello Solana!");
+    /// assert_eq!(loaded.update_count, 42);
+    ///
+    /// let uninitialized = UserData::construct(owner, String::new(), String::new(), 0, false).unwrap();
+    /// assert!(!uninitialized.is_initialized());
+    /// assert!(UserData::construct(owner, "n".repeat(UserData::MAX_NAME_LENGTH + 1), String::new(), 0, true).is_err());
+    /// ```
+    pub fn construct(
+        owner: Pubkey,
+        name: String,
+        message: String,
+        update_count: u64,
+        is_initialized: bool,
+    ) -> Result<Self, ProgramError> {
         if name.len() > Self::MAX_NAME_LENGTH {
             return Err(AccountDemoError::NameTooLong.into());
         }
+        #[cfg(feature = "sanitize-names")]
+        if name.chars().any(char::is_control) {
+            return Err(AccountDemoError::InvalidName.into());
+        }
         if message.len() > Self::MAX_MESSAGE_LENGTH {
             return Err(AccountDemoError::MessageTooLong.into());
         }
         
-        Ok(Self {
-            is_initialized: true,
-            owner: owner.to_bytes(),  // Convert Pubkey to bytes
+        let mut user_data = Self {
+            flags: if is_initialized { Self::FLAG_INITIALIZED } else { 0 },
+            owner: owner.into(),
             name,
             message,
-            update_count: 1,
-        })
+            update_count,
+            messages: Vec::new(),
+            alias: String::new(),
+            last_update_slot: 0,
+            checksum: 0,
+            avatar_hash: None,
+            label: String::new(),
+            name_updated_at: 0,
+            message_updated_at: 0,
+            bump: 0,
+        };
+        user_data.checksum = user_data.compute_checksum();
+        
+        Ok(user_data)
+    }
+    
+    pub fn is_initialized(&self) -> bool {
+        self.flags & Self::FLAG_INITIALIZED != 0
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.flags & Self::FLAG_FROZEN != 0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.flags & Self::FLAG_COMPRESSED != 0
+    }
+
+    pub fn is_verified(&self) -> bool {
+        self.flags & Self::FLAG_VERIFIED != 0
+    }
+
+    /// Whether the account has been verified as its owner's canonical PDA,
+    /// so `bump` can be trusted without re-deriving the address
+    pub fn is_canonical(&self) -> bool {
+        self.flags & Self::FLAG_CANONICAL != 0
+    }
+
+    /// Record `bump` as the verified canonical bump of this account
+    ///
+    /// The caller must already have checked the account address against
+    /// the owner's PDA.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// assert!(!user_data.is_canonical());
+    ///
+    /// user_data.set_canonical(254);
+    /// assert!(user_data.is_canonical());
+    /// assert_eq!(user_data.bump, 254);
+    /// ```
+    pub fn set_canonical(&mut self, bump: u8) {
+        self.bump = bump;
+        self.flags |= Self::FLAG_CANONICAL;
+    }
+
+    /// Set the bits of `mask` to the matching bits of `value`, leaving the
+    /// others untouched
+    ///
+    /// Only `OWNER_FLAGS` may be changed; any other bit in `mask` is
+    /// rejected so the initialized bit can't be cleared.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    ///
+    /// // Set both owner flags at once, then clear just one
+    /// user_data.set_flags(UserData::OWNER_FLAGS, UserData::OWNER_FLAGS).unwrap();
+    /// assert!(user_data.is_frozen() && user_data.is_compressed());
+    /// user_data.set_flags(UserData::FLAG_FROZEN, 0).unwrap();
+    /// assert!(!user_data.is_frozen() && user_data.is_compressed());
+    /// assert!(user_data.is_initialized());
+    ///
+    /// assert!(user_data.set_flags(UserData::FLAG_INITIALIZED, 0).is_err());
+    /// assert!(user_data.is_initialized());
+    /// ```
+    pub fn set_flags(&mut self, mask: u8, value: u8) -> Result<(), ProgramError> {
+        if mask & !Self::OWNER_FLAGS != 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        self.flags = (self.flags & !mask) | (value & mask);
+        Ok(())
     }
     
     pub fn get_owner(&self) -> Pubkey {
-        Pubkey::new_from_array(self.owner)  // Convert byte array back to Pubkey
+        self.owner.into()
+    }
+
+    /// Whether `key` is the stored owner, compared in constant time
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let owner = Pubkey::new_unique();
+    /// let user_data = UserData::new(owner, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// assert!(user_data.is_owner(&owner));
+    /// assert!(!user_data.is_owner(&Pubkey::new_unique()));
+    /// ```
+    pub fn is_owner(&self, key: &Pubkey) -> bool {
+        self.owner.ct_eq(key)
+    }
+
+    pub fn set_message_at(&mut self, index: u8, message: String) -> Result<(), ProgramError> {
+        let index = index as usize;
+        if index >= Self::MAX_SLOTS {
+            return Err(AccountDemoError::SlotOutOfRange.into());
+        }
+        if message.len() > Self::MAX_MESSAGE_LENGTH {
+            return Err(AccountDemoError::MessageTooLong.into());
+        }
+        
+        // Fill any skipped slots with empty messages
+        if self.messages.len() <= index {
+            self.messages.resize(index + 1, String::new());
+        }
+        self.messages[index] = message;
+        
+        let total_length: usize = self.messages.iter().map(|message| message.len()).sum();
+        if total_length > Self::MAX_TOTAL_MESSAGES_LENGTH {
+            return Err(AccountDemoError::MessagesTooLong.into());
+        }
+        
+        Ok(())
+    }
+
+    pub fn set_alias(&mut self, alias: String) -> Result<(), ProgramError> {
+        if alias.len() > Self::MAX_ALIAS_LENGTH {
+            return Err(AccountDemoError::AliasTooLong.into());
+        }
+        
+        self.alias = alias;
+        Ok(())
+    }
+
+    /// Set the free-form display label, or clear it with an empty string
+    ///
+    /// ```
+    /// use solana_clean_demo::{error::AccountDemoError, state::UserData};
+    /// use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let size = user_data.size_of_current();
+    /// user_data.set_label("Work account".to_string()).unwrap();
+    /// assert_eq!(user_data.size_of_current(), size + "Work account".len());
+    ///
+    /// assert_eq!(
+    ///     user_data.set_label("l".repeat(UserData::MAX_LABEL_LENGTH + 1)).unwrap_err(),
+    ///     ProgramError::from(AccountDemoError::LabelTooLong)
+    /// );
+    /// assert_eq!(user_data.label, "Work account");
+    /// ```
+    pub fn set_label(&mut self, label: String) -> Result<(), ProgramError> {
+        if label.len() > Self::MAX_LABEL_LENGTH {
+            return Err(AccountDemoError::LabelTooLong.into());
+        }
+        
+        self.label = label;
+        Ok(())
+    }
+
+    /// Collect the scalar fields returned by GetSummary
+    ///
+    /// ```
+    /// use borsh::{BorshDeserialize, BorshSerialize};
+    /// use solana_clean_demo::state::{Summary, UserData};
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let return_data = user_data.summary().try_to_vec().unwrap();
+    ///
+    /// let summary = Summary::try_from_slice(&return_data).unwrap();
+    /// assert_eq!(summary, Summary { update_count: 1, name_len: 8, message_len: 13 });
+    /// ```
+    pub fn summary(&self) -> Summary {
+        Summary {
+            update_count: self.update_count,
+            name_len: self.name.len() as u32,
+            message_len: self.message.len() as u32,
+        }
+    }
+
+    /// Split into the fixed-size header and the variable-length strings
+    ///
+    /// `from_header_and_strings` reverses this exactly, checksum included.
+    ///
+    /// ```
+    /// use borsh::BorshSerialize;
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let owner = Pubkey::new_unique();
+    /// let mut user_data = UserData::new(owner, "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// user_data.set_message_at(1, "slot".to_string()).unwrap();
+    /// user_data.alias = "jd".to_string();
+    ///
+    /// for avatar_hash in [None, Some([7u8; 32])] {
+    ///     user_data.avatar_hash = avatar_hash;
+    ///     let (header, strings) = user_data.clone().split();
+    ///     assert_eq!(header.owner, owner.to_bytes());
+    ///     assert_eq!(header.has_avatar_hash, avatar_hash.is_some() as u8);
+    ///     assert_eq!((strings.name.as_str(), strings.alias.as_str()), ("John Doe", "jd"));
+    ///
+    ///     let rebuilt = UserData::from_header_and_strings(header, strings);
+    ///     assert_eq!(rebuilt.try_to_vec().unwrap(), user_data.try_to_vec().unwrap());
+    /// }
+    /// ```
+    pub fn split(self) -> (UserDataHeader, UserDataStrings) {
+        let header = UserDataHeader {
+            update_count: self.update_count,
+            last_update_slot: self.last_update_slot,
+            name_updated_at: self.name_updated_at,
+            message_updated_at: self.message_updated_at,
+            checksum: self.checksum,
+            owner: self.owner.0,
+            avatar_hash: self.avatar_hash.unwrap_or_default(),
+            flags: self.flags,
+            has_avatar_hash: self.avatar_hash.is_some() as u8,
+            bump: self.bump,
+            reserved: [0; 1],
+        };
+        let strings = UserDataStrings {
+            name: self.name,
+            message: self.message,
+            messages: self.messages,
+            alias: self.alias,
+            label: self.label,
+        };
+        (header, strings)
+    }
+
+    /// Reassemble a UserData from the parts produced by `split`
+    ///
+    /// Any non-zero `has_avatar_hash` counts as set.
+    pub fn from_header_and_strings(header: UserDataHeader, strings: UserDataStrings) -> Self {
+        UserData {
+            flags: header.flags,
+            owner: OwnerKey(header.owner),
+            name: strings.name,
+            message: strings.message,
+            update_count: header.update_count,
+            messages: strings.messages,
+            alias: strings.alias,
+            last_update_slot: header.last_update_slot,
+            checksum: header.checksum,
+            avatar_hash: if header.has_avatar_hash != 0 { Some(header.avatar_hash) } else { None },
+            label: strings.label,
+            name_updated_at: header.name_updated_at,
+            message_updated_at: header.message_updated_at,
+            bump: header.bump,
+        }
+    }
+
+    pub fn compute_checksum(&self) -> u32 {
+        crc32(&[self.name.as_bytes(), self.message.as_bytes()])
     }
 
-    // Custom method to deserialize account data
+    /// Refresh the checksum and write the account data into `data`,
+    /// zeroing any space after it
+    ///
+    /// A buffer too small for the current data is rejected up front with
+    /// `AccountDataTooSmall` instead of failing part way through the write.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current() - 1];
+    /// assert_eq!(user_data.save(&mut data).unwrap_err(), ProgramError::AccountDataTooSmall);
+    ///
+    /// data.push(0);
+    /// assert!(user_data.save(&mut data).is_ok());
+    ///
+    /// // Bytes past the data, e.g. left over from a longer message, are zeroed
+    /// let mut data = vec![0xaa; user_data.size_of_current() + 16];
+    /// user_data.save(&mut data).unwrap();
+    /// assert!(data[user_data.size_of_current()..].iter().all(|byte| *byte == 0));
+    /// ```
+    pub fn save(&mut self, data: &mut [u8]) -> Result<(), ProgramError> {
+        let size = self.size_of_current();
+        if data.len() < size {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        
+        // Serialize first so a failure can't leave the account half written
+        self.checksum = self.compute_checksum();
+        let bytes = self.to_vec()?;
+        data[..bytes.len()].copy_from_slice(&bytes);
+        
+        // Don't leave stale bytes from earlier, longer data behind
+        data[bytes.len()..].fill(0);
+        
+        Ok(())
+    }
+
+    /// Serialize as current-version account data into a new buffer
+    ///
+    /// The checksum is written as it is; `save` refreshes it first.
+    /// Failures are reported as `SerializationFailed`.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// user_data.set_alias("johnd".to_string()).unwrap();
+    /// user_data.checksum = user_data.compute_checksum();
+    ///
+    /// let bytes = user_data.to_vec().unwrap();
+    /// assert_eq!(bytes.len(), user_data.size_of_current());
+    /// assert_eq!(UserData::safe_deserialize(&bytes).unwrap().alias, "johnd");
+    /// ```
+    pub fn to_vec(&self) -> Result<Vec<u8>, ProgramError> {
+        StoredUserData::V6(self.clone()).try_to_vec().map_err(|error| {
+            msg!("Error: Failed to serialize account data: {}", error);
+            AccountDemoError::SerializationFailed.into()
+        })
+    }
+
+    pub fn increment_update_count(&mut self) -> Result<(), ProgramError> {
+        self.update_count = self
+            .update_count
+            .checked_add(1)
+            .ok_or(ProgramError::InvalidArgument)?;
+        
+        Ok(())
+    }
+
+    /// Deserialize account data, failing with `NotInitialized` if it was
+    /// never initialized or has since been closed
+    ///
+    /// Closing zeroes the data, and a closed account keeps its zeroed data
+    /// until the end of the transaction, so any all-zero buffer is reported
+    /// as `NotInitialized`, whatever its length.
+    ///
+    /// ```
+    /// use solana_clean_demo::{error::AccountDemoError, state::UserData};
+    /// use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// for zeroed in [&[0u8; 128][..], &[0u8; 8][..]] {
+    ///     assert_eq!(
+    ///         UserData::load_initialized(zeroed).unwrap_err(),
+    ///         ProgramError::from(AccountDemoError::NotInitialized)
+    ///     );
+    /// }
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data).unwrap();
+    /// assert_eq!(UserData::load_initialized(&data).unwrap().name, "John Doe");
+    /// ```
+    pub fn load_initialized(data: &[u8]) -> Result<Self, ProgramError> {
+        // Never written, or wiped by CloseAccount
+        if data.iter().all(|byte| *byte == 0) {
+            return Err(AccountDemoError::NotInitialized.into());
+        }
+        
+        let user_data = Self::safe_deserialize(data)?;
+        if !user_data.is_initialized() {
+            return Err(AccountDemoError::NotInitialized.into());
+        }
+        
+        Ok(user_data)
+    }
+
+    /// Deserialize account data of any stored version, verifying its checksum
+    ///
+    /// ```
+    /// use solana_clean_demo::{error::AccountDemoError, state::UserData};
+    /// use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data).unwrap();
+    /// assert!(UserData::safe_deserialize(&data).is_ok());
+    ///
+    /// // Flip a byte of the name behind the program's back
+    /// data[38] ^= 0x20;
+    /// assert_eq!(
+    ///     UserData::safe_deserialize(&data).unwrap_err(),
+    ///     ProgramError::from(AccountDemoError::ChecksumMismatch)
+    /// );
+    /// ```
     pub fn safe_deserialize(data: &[u8]) -> Result<Self, ProgramError> {
         // Read only what we need and ignore the rest
-        let mut user_data = Self::try_from_slice(data)
-            .map_err(|_| AccountDemoError::DataTypeMismatch)?;
+        let user_data = StoredUserData::from_account_data(data)?.into_latest();
+        
+        if user_data.checksum != user_data.compute_checksum() {
+            return Err(AccountDemoError::ChecksumMismatch.into());
+        }
         
         Ok(user_data)
     }
+
+    /// Read update_count straight from account data without deserializing
+    ///
+    /// Every stored version shares the same prefix up to the counter, so only
+    /// the two string length prefixes need parsing to find its offset.
+    ///
+    /// ```
+    /// use solana_clean_demo::state::UserData;
+    /// use solana_program::pubkey::Pubkey;
+    ///
+    /// let mut user_data = UserData::new(Pubkey::new_unique(), "John Doe".to_string(), "Hello Solana!".to_string()).unwrap();
+    /// user_data.increment_update_count().unwrap();
+    /// let mut data = vec![0u8; user_data.size_of_current()];
+    /// user_data.save(&mut data).unwrap();
+    ///
+    /// let full = UserData::safe_deserialize(&data).unwrap();
+    /// assert_eq!(UserData::read_update_count(&data).unwrap(), full.update_count);
+    /// assert!(UserData::read_update_count(&data[..40]).is_err());
+    /// ```
+    pub fn read_update_count(data: &[u8]) -> Result<u64, ProgramError> {
+        // version (1) + flags (1) + owner (32)
+        let mut offset = 1 + 1 + 32;
+        match data.first() {
+            // Either V2 or the untagged original layout, which has no version byte
+            Some(1) => return Ok(StoredUserData::from_account_data(data)?.into_latest().update_count),
+            Some(version) if *version <= 5 => {}
+            _ => return Err(AccountDemoError::DataTypeMismatch.into()),
+        }
+
+        // Skip name and message
+        for _ in 0..2 {
+            let len = read_array::<4>(data, offset)?;
+            offset = (offset + 4)
+                .checked_add(u32::from_le_bytes(len) as usize)
+                .ok_or(AccountDemoError::DataTypeMismatch)?;
+        }
+
+        Ok(u64::from_le_bytes(read_array::<8>(data, offset)?))
+    }
+}
+
+/// Serialize `value` into the start of `data`
+///
+/// Failures are reported as `SerializationFailed`, so a write that doesn't
+/// fit can't be mistaken for corrupt data read back with `DataTypeMismatch`.
+///
+/// ```
+/// use solana_clean_demo::{error::AccountDemoError, state::{write_account_data, Config}};
+/// use solana_program::program_error::ProgramError;
+///
+/// let config = Config { is_initialized: true, admin: [7; 32], paused: false, attester: [0; 32] };
+/// let mut data = vec![0u8; Config::LEN - 1];
+/// assert_eq!(
+///     write_account_data(&config, &mut data).unwrap_err(),
+///     ProgramError::from(AccountDemoError::SerializationFailed)
+/// );
+///
+/// data.push(0);
+/// assert!(write_account_data(&config, &mut data).is_ok());
+/// ```
+pub fn write_account_data<T: BorshSerialize>(value: &T, data: &mut [u8]) -> Result<(), ProgramError> {
+    value.serialize(&mut &mut data[..]).map_err(|error| {
+        msg!("Error: Failed to serialize account data: {}", error);
+        AccountDemoError::SerializationFailed.into()
+    })
+}
+
+fn read_array<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], ProgramError> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| AccountDemoError::DataTypeMismatch.into())
+}
+
+// CRC32 (IEEE), computed bitwise to avoid a lookup table on-chain
+fn crc32(chunks: &[&[u8]]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for chunk in chunks {
+        for &byte in chunk.iter() {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (crc & 1).wrapping_neg();
+                crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+    !crc
 }
 
 pub trait StringPad {
@@ -0,0 +1,50 @@
+use constant_time_eq::constant_time_eq;
+use solana_program::hash::{hashv, HASH_BYTES};
+
+/// Fixed key for the demo tag. A real program would never embed its key
+/// in public code; this only exists to show constant-time verification.
+pub const TAG_KEY: &[u8] = b"solana-clean-demo-tag-key";
+
+/// Compute the keyed tag of a message: `sha256(TAG_KEY || message)`
+pub fn compute_tag(message: &str) -> [u8; HASH_BYTES] {
+    hashv(&[TAG_KEY, message.as_bytes()]).to_bytes()
+}
+
+/// Check `expected` against the tag of `message` in constant time
+///
+/// ```
+/// use solana_clean_demo::tag::{compute_tag, verify_tag};
+///
+/// let tag = compute_tag("Hello Solana!");
+/// assert!(verify_tag("Hello Solana!", &tag));
+/// assert!(!verify_tag("Hello Solana?", &tag));
+/// assert!(!verify_tag("Hello Solana!", &tag[..31]));
+/// ```
+pub fn verify_tag(message: &str, expected: &[u8]) -> bool {
+    let mut tag = compute_tag(message);
+    let matches = constant_time_eq(&tag, expected);
+    zeroize(&mut tag);
+    matches
+}
+
+/// Overwrite a buffer holding secret-derived bytes with zeros
+///
+/// The writes are volatile so the compiler can't drop them as dead stores
+/// before the buffer goes out of scope. This is best effort only: copies
+/// the compiler made elsewhere, such as in registers or moved values, are
+/// not cleared.
+///
+/// ```
+/// use solana_clean_demo::tag::{compute_tag, zeroize};
+///
+/// let mut tag = compute_tag("Hello Solana!");
+/// zeroize(&mut tag);
+/// assert!(tag.iter().all(|byte| *byte == 0));
+/// ```
+pub fn zeroize(buf: &mut [u8]) {
+    for byte in buf.iter_mut() {
+        // SAFETY: `byte` is a valid, aligned, exclusive reference
+        unsafe { core::ptr::write_volatile(byte, 0) };
+    }
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+}
@@ -0,0 +1,91 @@
+//! Helpers for running the program off-chain in tests
+//!
+//! Only built with the `test-support` feature.
+
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use solana_program::{clock::Clock, program_stubs, rent::Rent};
+
+thread_local! {
+    // Lines logged on this thread while a capture is running
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+// The syscall stubs are process-wide, so captures must not overlap
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+// Records every log line, and serves a default Clock and Rent so
+// instructions that read sysvars can run
+struct LogCapture;
+
+impl program_stubs::SyscallStubs for LogCapture {
+    fn sol_log(&self, message: &str) {
+        LOGS.with(|logs| logs.borrow_mut().push(message.to_string()));
+    }
+
+    // Nothing is metered off-chain
+    fn sol_log_compute_units(&self) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+}
+
+// Puts the previous stubs back and ends the capture, even if the captured
+// closure panics
+struct Capture(Option<Box<dyn program_stubs::SyscallStubs>>);
+
+impl Capture {
+    fn start() -> Self {
+        while CAPTURING
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            thread::yield_now();
+        }
+        LOGS.with(|logs| logs.borrow_mut().clear());
+        Capture(Some(program_stubs::set_syscall_stubs(Box::new(LogCapture))))
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        if let Some(stubs) = self.0.take() {
+            program_stubs::set_syscall_stubs(stubs);
+        }
+        CAPTURING.store(false, Ordering::Release);
+    }
+}
+
+/// Run `f` and return every line it logged with `msg!`, in order
+///
+/// While `f` runs, the sysvar syscalls return `Clock::default()` and
+/// `Rent::default()`, and compute unit logs are dropped. Any stubs
+/// installed before are restored afterwards. Captures from parallel tests
+/// take turns. With the `no-logs` feature nothing is logged, so the result
+/// is always empty.
+///
+/// ```
+/// use solana_clean_demo::test_support::capture_logs;
+/// use solana_program::msg;
+///
+/// let logs = capture_logs(|| msg!("Hello {}", 42));
+/// assert_eq!(logs, vec!["Hello 42".to_string()]);
+/// ```
+pub fn capture_logs<F: FnOnce()>(f: F) -> Vec<String> {
+    let capture = Capture::start();
+    f();
+    drop(capture);
+
+    LOGS.with(|logs| logs.take())
+}
@@ -0,0 +1,48 @@
+//! Log-based assertions on whole instructions, through `capture_logs`.
+#![cfg(feature = "test-support")]
+
+use borsh::BorshSerialize;
+use solana_clean_demo::{
+    entrypoint::process_instruction, instruction::AccountDemoInstruction, pda, state::UserData,
+    test_support::capture_logs,
+};
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+
+#[test]
+fn initialize_logs_each_step() {
+    let program_id = Pubkey::new_unique();
+    let owner_key = Pubkey::new_unique();
+    let (data_key, _) = pda::find_user_data_address(&owner_key, &program_id);
+    let (config_key, _) = pda::find_config_address(&program_id);
+    let system_key = solana_program::system_program::id();
+
+    // The account already exists, so no CPI is needed
+    let (mut owner_lamports, mut data_lamports, mut system_lamports, mut config_lamports) = (0, 1_000_000, 0, 0);
+    let (mut owner_data, mut data_data, mut system_data, mut config_data) = (vec![], vec![0u8; 128], vec![], vec![]);
+    let accounts = [
+        AccountInfo::new(&owner_key, true, true, &mut owner_lamports, &mut owner_data, &system_key, false, 0),
+        AccountInfo::new(&data_key, false, true, &mut data_lamports, &mut data_data, &program_id, false, 0),
+        AccountInfo::new(&system_key, false, false, &mut system_lamports, &mut system_data, &system_key, true, 0),
+        AccountInfo::new(&config_key, false, false, &mut config_lamports, &mut config_data, &program_id, false, 0),
+    ];
+    let initialize = AccountDemoInstruction::Initialize { name: "John Doe".to_string(), message: "Hi".to_string() }
+        .try_to_vec()
+        .unwrap();
+
+    let logs = capture_logs(|| process_instruction(&program_id, &accounts, &initialize).unwrap());
+    assert_eq!(UserData::load_initialized(&accounts[1].data.borrow()).unwrap().name, "John Doe");
+
+    if cfg!(feature = "no-logs") {
+        assert!(logs.is_empty());
+        return;
+    }
+    let expected = [
+        "Instruction: Initialize { name: John Doe, message: Hi }",
+        "Account already exists",
+        "Account data initialized successfully",
+    ];
+    let mut remaining = logs.iter();
+    for line in expected {
+        assert!(remaining.any(|log| log == line), "missing or out of order: {:?} in {:#?}", line, logs);
+    }
+}
@@ -0,0 +1,149 @@
+//! Round-trips random valid UserData through `save` and `safe_deserialize`,
+//! so a Borsh layout change or a wrong size calculation shows up here.
+//! Also loads data in the original, untagged layout.
+
+use borsh::BorshSerialize;
+use proptest::prelude::*;
+use solana_clean_demo::state::UserData;
+use solana_program::pubkey::Pubkey;
+
+// Printable ASCII, so byte and char lengths agree and sanitize-names passes
+fn text(max_len: usize) -> impl Strategy<Value = String> {
+    proptest::string::string_regex(&format!("[ -~]{{0,{}}}", max_len)).unwrap()
+}
+
+prop_compose! {
+    fn user_data()(
+        owner in any::<[u8; 32]>(),
+        name in text(UserData::MAX_NAME_LENGTH),
+        message in text(UserData::MAX_MESSAGE_LENGTH),
+        update_count in any::<u64>(),
+        flags in any::<u8>(),
+        messages in prop::collection::vec(
+            text(UserData::MAX_TOTAL_MESSAGES_LENGTH / UserData::MAX_SLOTS),
+            0..=UserData::MAX_SLOTS,
+        ),
+        alias in text(UserData::MAX_ALIAS_LENGTH),
+        last_update_slot in any::<u64>(),
+        avatar_hash in any::<Option<[u8; 32]>>(),
+        label in text(UserData::MAX_LABEL_LENGTH),
+        name_updated_at in any::<i64>(),
+        message_updated_at in any::<i64>(),
+        bump in any::<u8>(),
+    ) -> UserData {
+        let mut user_data = UserData::new(Pubkey::new_from_array(owner), name, message).unwrap();
+        user_data.update_count = update_count;
+        user_data.flags |= flags & (UserData::OWNER_FLAGS | UserData::FLAG_VERIFIED | UserData::FLAG_CANONICAL);
+        user_data.messages = messages;
+        user_data.alias = alias;
+        user_data.last_update_slot = last_update_slot;
+        user_data.avatar_hash = avatar_hash;
+        user_data.label = label;
+        user_data.name_updated_at = name_updated_at;
+        user_data.message_updated_at = message_updated_at;
+        user_data.bump = bump;
+        user_data
+    }
+}
+
+// Account data as the original program wrote it: is_initialized, owner,
+// name, message and update_count, with no layout version in front
+fn original_layout(owner: &[u8; 32], name: &str, message: &str, update_count: u64) -> Vec<u8> {
+    let mut data = vec![1];
+    data.extend_from_slice(owner);
+    data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    data.extend_from_slice(name.as_bytes());
+    data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    data.extend_from_slice(message.as_bytes());
+    data.extend_from_slice(&update_count.to_le_bytes());
+    data
+}
+
+fn assert_roundtrips(mut user_data: UserData) {
+    // size_of_current must be exactly what save writes
+    let size = user_data.size_of_current();
+    let mut data = vec![0u8; size];
+    assert!(user_data.save(&mut data[..size - 1]).is_err());
+    user_data.save(&mut data).unwrap();
+
+    let restored = UserData::safe_deserialize(&data).unwrap();
+    assert_eq!(restored.name, user_data.name);
+    assert_eq!(restored.message, user_data.message);
+    assert_eq!(restored.get_owner(), user_data.get_owner());
+    assert_eq!(restored.try_to_vec().unwrap(), user_data.try_to_vec().unwrap());
+}
+
+proptest! {
+    #[test]
+    fn save_then_safe_deserialize_roundtrips(user_data in user_data()) {
+        assert_roundtrips(user_data);
+    }
+
+    #[test]
+    fn untagged_original_layout_loads(
+        owner in any::<[u8; 32]>(),
+        name in text(UserData::MAX_NAME_LENGTH),
+        message in text(UserData::MAX_MESSAGE_LENGTH),
+        update_count in any::<u64>(),
+        padding in 0..64usize,
+    ) {
+        let mut data = original_layout(&owner, &name, &message, update_count);
+        data.resize(data.len() + padding, 0);
+
+        let user_data = UserData::load_initialized(&data).unwrap();
+        prop_assert_eq!(user_data.get_owner(), Pubkey::new_from_array(owner));
+        prop_assert_eq!(user_data.name, name);
+        prop_assert_eq!(user_data.message, message);
+        prop_assert_eq!(user_data.update_count, update_count);
+        prop_assert_eq!(UserData::read_update_count(&data).unwrap(), update_count);
+    }
+
+    // Any strings, including multi-byte and over-long ones
+    #[test]
+    fn get_size_matches_serialized_size(name in any::<String>(), message in any::<String>()) {
+        prop_assert_eq!(UserData::get_size(&name, &message), UserData::serialized_size(&name, &message));
+    }
+}
+
+#[test]
+fn empty_fields_roundtrip() {
+    let mut user_data = UserData::new(Pubkey::default(), String::new(), String::new()).unwrap();
+    user_data.update_count = 0;
+    assert_roundtrips(user_data);
+}
+
+#[test]
+fn max_length_fields_roundtrip() {
+    let mut user_data = UserData::new(
+        Pubkey::new_from_array([0xff; 32]),
+        "n".repeat(UserData::MAX_NAME_LENGTH),
+        "m".repeat(UserData::MAX_MESSAGE_LENGTH),
+    )
+    .unwrap();
+    let slot = "s".repeat(UserData::MAX_TOTAL_MESSAGES_LENGTH / UserData::MAX_SLOTS);
+    user_data.messages = vec![slot; UserData::MAX_SLOTS];
+    user_data.alias = "a".repeat(UserData::MAX_ALIAS_LENGTH);
+    user_data.update_count = u64::MAX;
+    user_data.last_update_slot = u64::MAX;
+    user_data.flags = u8::MAX;
+    user_data.avatar_hash = Some([0xff; 32]);
+    user_data.label = "l".repeat(UserData::MAX_LABEL_LENGTH);
+    user_data.name_updated_at = i64::MAX;
+    user_data.message_updated_at = i64::MIN;
+    user_data.bump = u8::MAX;
+    assert_roundtrips(user_data);
+}
+
+#[test]
+fn original_account_bytes_load() {
+    // An account the original program initialized for "John Doe"
+    let owner = [7u8; 32];
+    let data = original_layout(&owner, "John Doe", "Hello Solana!", 1);
+    assert_eq!(data.len(), 1 + 32 + 4 + 8 + 4 + 13 + 8);
+
+    let user_data = UserData::load_initialized(&data).unwrap();
+    assert!(user_data.is_owner(&Pubkey::new_from_array(owner)));
+    assert_eq!(user_data.name, "John Doe");
+    assert_eq!(user_data.message, "Hello Solana!");
+    assert_eq!(user_data.update_count, 1);
+}